@@ -8,13 +8,34 @@
 
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse_macro_input, ItemFn};
+use syn::{
+    parse_macro_input, FnArg, GenericArgument, ItemFn, Path, PathArguments, ReturnType, Type,
+    Visibility,
+};
 
 /// A procedural macro that exports a simple function for use with a WebWorker.
 ///
-/// Use this for functions that take a single argument and return a result synchronously.
+/// Use this for functions that do not need to communicate with the main thread while running.
 /// The function will be callable via `WebWorkerFn` and the `webworker!` macro.
 ///
+/// The function may also be `async`, e.g., to await a `JsFuture` inside the worker.
+/// Its result is sent back once the future resolves.
+/// Other tasks can run on the same worker while the future is pending.
+///
+/// Functions with a single parameter take their argument as is.
+/// Functions with zero or multiple parameters take a tuple of all arguments instead,
+/// e.g., `()` or `(a, b)`.
+///
+/// If the function panics inside the worker, the panic message and location
+/// are reported back to the caller as `TaskError::Panic`.
+///
+/// Functions taking and returning `Box<[u8]>` are additionally exported
+/// for use with `run_transfer`, which passes the bytes without (de)serialization.
+///
+/// By default, the argument and the result are serialized using `wasmworker::convert::DefaultCodec`.
+/// A different `wasmworker::convert::Codec` can be chosen per function using
+/// `#[webworker_fn(codec = path::to::Codec)]`. The `webworker!` macro picks up the codec automatically.
+///
 /// # Example
 ///
 /// ```ignore
@@ -25,24 +46,158 @@ use syn::{parse_macro_input, ItemFn};
 ///     v.sort();
 ///     v
 /// }
+///
+/// // Called with `worker.run(webworker!(add), &(1, 2))`.
+/// #[webworker_fn]
+/// fn add(a: u32, b: u32) -> u32 {
+///     a + b
+/// }
+///
+/// #[webworker_fn(codec = wasmworker::convert::JsonCodec)]
+/// fn count_words(text: String) -> usize {
+///     text.split_whitespace().count()
+/// }
 /// ```
 #[proc_macro_attribute]
-pub fn webworker_fn(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn webworker_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut codec: Option<Path> = None;
+    let attr_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("codec") {
+            codec = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported webworker_fn property"))
+        }
+    });
+    parse_macro_input!(attr with attr_parser);
+    let codec = codec.map_or_else(
+        || quote! { wasmworker::convert::DefaultCodec },
+        |codec| quote! { #codec },
+    );
+
     let input = parse_macro_input!(item as ItemFn);
     let fn_name = &input.sig.ident;
     let wrapper_fn_name = format_ident!("__webworker_{}", fn_name);
 
+    // The adapter takes all arguments at once, either as is or as a tuple.
+    let mut arg_types = Vec::new();
+    for arg in &input.sig.inputs {
+        match arg {
+            FnArg::Typed(arg) => arg_types.push(&*arg.ty),
+            FnArg::Receiver(receiver) => {
+                return syn::Error::new_spanned(receiver, "webworker_fn cannot take `self`")
+                    .to_compile_error()
+                    .into();
+            }
+        }
+    }
+    let arg_names: Vec<_> = (0..arg_types.len())
+        .map(|i| format_ident!("arg{}", i))
+        .collect();
+    let (call_arg, call_arg_type) = match (&arg_names[..], &arg_types[..]) {
+        ([name], [ty]) => (quote! { #name }, quote! { #ty }),
+        _ => (quote! { (#(#arg_names,)*) }, quote! { (#(#arg_types,)*) }),
+    };
+    let ret_type = match &input.sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+    let call_vis = child_visibility(&input.vis);
+
+    // Raw byte functions can be called without (de)serialization.
+    let is_async = input.sig.asyncness.is_some();
+    let transfer_code = if is_byte_fn(&input) {
+        let transfer_fn_name = format_ident!("__webworker_transfer_{}", fn_name);
+        if is_async {
+            quote! {
+                #[wasm_bindgen::prelude::wasm_bindgen]
+                pub async fn #transfer_fn_name(arg: Box<[u8]>) -> Result<Box<[u8]>, wasm_bindgen::JsValue> {
+                    wasmworker::panic::install_panic_hook();
+                    wasmworker::panic::catch_panic_async(super::#fn_name(arg)).await
+                }
+            }
+        } else {
+            quote! {
+                #[wasm_bindgen::prelude::wasm_bindgen]
+                pub fn #transfer_fn_name(arg: Box<[u8]>) -> Result<Box<[u8]>, wasm_bindgen::JsValue> {
+                    wasmworker::panic::install_panic_hook();
+                    wasmworker::panic::catch_panic(|| super::#fn_name(arg))
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let (call_code, constructor, wrapper_code) = if is_async {
+        (
+            quote! {
+                /// Calls the function with all arguments at once.
+                #call_vis fn __call(
+                    #call_arg: #call_arg_type,
+                ) -> ::std::pin::Pin<Box<dyn ::std::future::Future<Output = #ret_type>>> {
+                    Box::pin(super::#fn_name(#(#arg_names),*))
+                }
+            },
+            quote! { with_codec_async_unchecked },
+            quote! {
+                #[wasm_bindgen::prelude::wasm_bindgen]
+                pub async fn #wrapper_fn_name(arg: Box<[u8]>) -> Result<Box<[u8]>, wasm_bindgen::JsValue> {
+                    use wasmworker::convert::Codec;
+                    wasmworker::panic::install_panic_hook();
+                    wasmworker::panic::catch_panic_async(async move {
+                        let arg = __Codec::decode(&arg).expect("WebWorker deserialization failed");
+                        let res = __call(arg).await;
+                        __Codec::encode(&res).expect("WebWorker serialization failed")
+                    })
+                    .await
+                }
+            },
+        )
+    } else {
+        (
+            quote! {
+                /// Calls the function with all arguments at once.
+                #call_vis fn __call(#call_arg: #call_arg_type) -> #ret_type {
+                    super::#fn_name(#(#arg_names),*)
+                }
+            },
+            quote! { with_codec_unchecked },
+            quote! {
+                #[wasm_bindgen::prelude::wasm_bindgen]
+                pub fn #wrapper_fn_name(arg: Box<[u8]>) -> Result<Box<[u8]>, wasm_bindgen::JsValue> {
+                    use wasmworker::convert::Codec;
+                    wasmworker::panic::install_panic_hook();
+                    wasmworker::panic::catch_panic(|| {
+                        let arg = __Codec::decode(&arg).expect("WebWorker deserialization failed");
+                        let res = __call(arg);
+                        __Codec::encode(&res).expect("WebWorker serialization failed")
+                    })
+                }
+            },
+        )
+    };
+
     let mod_code = quote! {
         #[doc(hidden)]
         pub mod #fn_name {
+            #[allow(unused_imports)]
+            use super::*;
+
             pub const __WEBWORKER: () = ();
+            pub type __Codec = #codec;
+
+            #call_code
+
+            /// Creates the [`wasmworker::func::WebWorkerFn`] for this function.
+            #call_vis fn __new() -> wasmworker::func::WebWorkerFn<#call_arg_type, #ret_type, __Codec> {
+                wasmworker::func::WebWorkerFn::#constructor(stringify!(#fn_name), __call)
+            }
+
             const _: () = {
-                #[wasm_bindgen::prelude::wasm_bindgen]
-                pub fn #wrapper_fn_name(arg: Box<[u8]>) -> Box<[u8]> {
-                    let arg = wasmworker::convert::from_bytes(&arg);
-                    let res = super::#fn_name(arg);
-                    wasmworker::convert::to_bytes(&res)
-                }
+                #wrapper_code
+
+                #transfer_code
             };
         }
     };
@@ -60,10 +215,18 @@ pub fn webworker_fn(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// Use this for functions that need bidirectional communication with the main thread,
 /// such as progress reporting or interactive workflows. The function must be async and
-/// take a `Channel` as its second parameter.
+/// take a `Channel` or a `TypedChannel<In, Out>` as its second parameter.
+/// With a `TypedChannel`, the `ChannelTask` on the main thread only sends `In`
+/// and receives `Out` messages.
+///
+/// The channel is closed once the function returns, so that the main thread's
+/// `ChannelTask::recv` returns `None` after the last message.
 ///
 /// The function will be callable via `WebWorkerChannelFn` and the `webworker_channel!` macro.
 ///
+/// In contrast to `#[webworker_fn]`, a codec cannot be chosen: the argument, the result,
+/// and the messages are always serialized using `wasmworker::convert::DefaultCodec`.
+///
 /// # Example
 ///
 /// ```ignore
@@ -79,26 +242,156 @@ pub fn webworker_fn(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// }
 /// ```
 #[proc_macro_attribute]
-pub fn webworker_channel_fn(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn webworker_channel_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr_parser = no_properties("webworker_channel_fn");
+    parse_macro_input!(attr with attr_parser);
     let input = parse_macro_input!(item as ItemFn);
     let fn_name = &input.sig.ident;
     let wrapper_fn_name = format_ident!("__webworker_channel_{}", fn_name);
 
+    let arg_types: Vec<_> = input
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(arg) => Some(&*arg.ty),
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+    let [arg_type, channel_type] = arg_types[..] else {
+        return syn::Error::new_spanned(
+            &input.sig.inputs,
+            "webworker_channel_fn must take an argument and a channel",
+        )
+        .to_compile_error()
+        .into();
+    };
+    let ret_type = match &input.sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+    let call_vis = child_visibility(&input.vis);
+
     let mod_code = quote! {
         #[doc(hidden)]
         pub mod #fn_name {
+            #[allow(unused_imports)]
+            use super::*;
+
             pub const __WEBWORKER_CHANNEL: () = ();
+
+            /// Creates the [`wasmworker::func::WebWorkerChannelFn`] for this function.
+            #call_vis fn __new() -> wasmworker::func::WebWorkerChannelFn<
+                #arg_type,
+                #ret_type,
+                <#channel_type as wasmworker::WorkerChannel>::In,
+                <#channel_type as wasmworker::WorkerChannel>::Out,
+            > {
+                wasmworker::func::WebWorkerChannelFn::with_channel_unchecked::<#channel_type>(
+                    stringify!(#fn_name),
+                    |arg, channel| Box::pin(super::#fn_name(arg, channel.into())),
+                )
+            }
+
+            const _: () = {
+                #[wasm_bindgen::prelude::wasm_bindgen]
+                pub async fn #wrapper_fn_name(arg: Box<[u8]>, port: wasm_bindgen::JsValue, capacity: Option<u32>) -> Result<Box<[u8]>, wasm_bindgen::JsValue> {
+                    use wasm_bindgen::JsCast;
+                    wasmworker::panic::install_panic_hook();
+                    wasmworker::panic::catch_panic_async(async move {
+                        let arg = wasmworker::convert::from_bytes(&arg);
+                        let channel = port
+                            .dyn_into::<wasmworker::MessagePort>()
+                            .map(|port| wasmworker::Channel::with_capacity(port, capacity))
+                            .expect("webworker_channel_fn requires a MessagePort");
+                        let res = super::#fn_name(arg, channel.clone().into()).await;
+                        // Let the main thread know that no more messages follow.
+                        channel.close();
+                        wasmworker::convert::to_bytes(&res)
+                    })
+                    .await
+                }
+            };
+        }
+    };
+
+    let expanded = quote! {
+        #input
+
+        #mod_code
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// A procedural macro that exports a function producing a stream of items for use with a WebWorker.
+///
+/// The function must take a single parameter and return an `impl Stream<Item = T>`,
+/// which may await other futures while producing its items.
+/// Each item is sent to the main thread as soon as it is available,
+/// but the worker only runs a limited number of items ahead of the consumer.
+///
+/// The function will be callable via `WebWorkerStreamFn` and the `webworker_stream!` macro.
+///
+/// In contrast to `#[webworker_fn]`, a codec cannot be chosen: the argument and the items
+/// are always serialized using `wasmworker::convert::DefaultCodec`.
+///
+/// # Example
+///
+/// ```ignore
+/// use futures::Stream;
+/// use wasmworker_proc_macro::webworker_stream_fn;
+///
+/// #[webworker_stream_fn]
+/// fn count_to(n: u32) -> impl Stream<Item = u32> {
+///     futures::stream::iter(1..=n)
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn webworker_stream_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr_parser = no_properties("webworker_stream_fn");
+    parse_macro_input!(attr with attr_parser);
+    let input = parse_macro_input!(item as ItemFn);
+    let fn_name = &input.sig.ident;
+    let wrapper_fn_name = format_ident!("__webworker_channel_{}", fn_name);
+
+    if let Some(asyncness) = &input.sig.asyncness {
+        return syn::Error::new_spanned(
+            asyncness,
+            "webworker_stream_fn cannot be async, return a stream instead",
+        )
+        .to_compile_error()
+        .into();
+    }
+    if input.sig.inputs.len() != 1 {
+        return syn::Error::new_spanned(
+            &input.sig.inputs,
+            "webworker_stream_fn must take exactly one parameter",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let mod_code = quote! {
+        #[doc(hidden)]
+        pub mod #fn_name {
+            pub const __WEBWORKER_STREAM: () = ();
             const _: () = {
                 #[wasm_bindgen::prelude::wasm_bindgen]
-                pub async fn #wrapper_fn_name(arg: Box<[u8]>, port: wasm_bindgen::JsValue) -> Box<[u8]> {
+                pub async fn #wrapper_fn_name(arg: Box<[u8]>, port: wasm_bindgen::JsValue, capacity: Option<u32>) -> Result<Box<[u8]>, wasm_bindgen::JsValue> {
                     use wasm_bindgen::JsCast;
-                    let arg = wasmworker::convert::from_bytes(&arg);
-                    let channel = port
-                        .dyn_into::<wasmworker::MessagePort>()
-                        .map(wasmworker::Channel::from)
-                        .expect("webworker_channel_fn requires a MessagePort");
-                    let res = super::#fn_name(arg, channel).await;
-                    wasmworker::convert::to_bytes(&res)
+                    wasmworker::panic::install_panic_hook();
+                    wasmworker::panic::catch_panic_async(async move {
+                        let arg = wasmworker::convert::from_bytes(&arg);
+                        let channel = port
+                            .dyn_into::<wasmworker::MessagePort>()
+                            .map(|port| wasmworker::Channel::with_capacity(port, capacity))
+                            .expect("webworker_stream_fn requires a MessagePort");
+                        wasmworker::stream::forward_stream(super::#fn_name(arg), channel.clone()).await;
+                        channel.close();
+                        wasmworker::convert::to_bytes(&())
+                    })
+                    .await
                 }
             };
         }
@@ -112,3 +405,69 @@ pub fn webworker_channel_fn(_attr: TokenStream, item: TokenStream) -> TokenStrea
 
     TokenStream::from(expanded)
 }
+
+/// Rejects all properties of a macro that does not take any,
+/// with a dedicated error for the `codec` property of `#[webworker_fn]`.
+fn no_properties(macro_name: &'static str) -> impl syn::parse::Parser<Output = ()> {
+    syn::meta::parser(move |meta| {
+        if meta.path.is_ident("codec") {
+            Err(meta.error(format!(
+                "{macro_name} does not support choosing a codec, it always uses `wasmworker::convert::DefaultCodec`"
+            )))
+        } else {
+            Err(meta.error(format!("unsupported {macro_name} property")))
+        }
+    })
+}
+
+/// Returns whether the function takes a single `Box<[u8]>` and returns a `Box<[u8]>`.
+fn is_byte_fn(input: &ItemFn) -> bool {
+    let mut inputs = input.sig.inputs.iter();
+    let (Some(FnArg::Typed(arg)), None) = (inputs.next(), inputs.next()) else {
+        return false;
+    };
+    let ReturnType::Type(_, output) = &input.sig.output else {
+        return false;
+    };
+    is_byte_box(&arg.ty) && is_byte_box(output)
+}
+
+/// Returns whether the type is `Box<[u8]>`.
+fn is_byte_box(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return false;
+    };
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return false;
+    };
+    let Some(GenericArgument::Type(Type::Slice(slice))) = args.args.first() else {
+        return false;
+    };
+    segment.ident == "Box"
+        && args.args.len() == 1
+        && matches!(&*slice.elem, Type::Path(elem) if elem.path.is_ident("u8"))
+}
+
+/// Maps the visibility of a function to the equivalent visibility
+/// of an item in the module generated for it.
+fn child_visibility(vis: &Visibility) -> proc_macro2::TokenStream {
+    match vis {
+        Visibility::Public(_) => quote! { pub },
+        Visibility::Inherited => quote! { pub(super) },
+        Visibility::Restricted(restricted) => {
+            let path = &restricted.path;
+            if path.is_ident("crate") || path.leading_colon.is_some() {
+                quote! { #vis }
+            } else if path.is_ident("self") {
+                quote! { pub(super) }
+            } else if path.segments.first().is_some_and(|s| s.ident == "crate") {
+                quote! { pub(in #path) }
+            } else {
+                quote! { pub(in super::#path) }
+            }
+        }
+    }
+}
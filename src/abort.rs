@@ -0,0 +1,78 @@
+use std::{cell::RefCell, future::Future, rc::Rc};
+
+use futures::future::{AbortRegistration, Abortable};
+
+use crate::{error::TaskError, webworker::WorkerHandle};
+
+/// A handle to cancel a task started with [`crate::WebWorker::run_cancellable`]
+/// or [`crate::pool::WebWorkerPool::run_cancellable`].
+///
+/// Aborting a task makes its future resolve to [`TaskError::Cancelled`],
+/// removes the task from the worker's bookkeeping, and tells the worker
+/// to drop the result once the function returns.
+/// Since a running function cannot be interrupted, a long-running task
+/// keeps its worker busy until it returns. Use [`AbortHandle::abort_and_terminate`]
+/// to terminate the worker instead.
+#[derive(Clone)]
+pub struct AbortHandle {
+    /// The handle for the wrapped future.
+    handle: futures::future::AbortHandle,
+    /// The worker the task has been scheduled on, if any.
+    worker: Rc<RefCell<Option<WorkerHandle>>>,
+}
+
+impl AbortHandle {
+    /// Creates a new handle together with the registration for the task's future.
+    pub(crate) fn new_pair() -> (Self, AbortRegistration) {
+        let (handle, registration) = futures::future::AbortHandle::new_pair();
+        (
+            Self {
+                handle,
+                worker: Rc::new(RefCell::new(None)),
+            },
+            registration,
+        )
+    }
+
+    /// Remembers the worker the task has been scheduled on.
+    pub(crate) fn set_worker(&self, worker: WorkerHandle) {
+        *self.worker.borrow_mut() = Some(worker);
+    }
+
+    /// Cancels the task.
+    ///
+    /// The worker still finishes the function if it is already running,
+    /// but its result is discarded.
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+
+    /// Cancels the task and terminates the worker it is running on.
+    ///
+    /// This is useful for CPU-bound tasks that would otherwise keep the worker busy.
+    /// All other tasks on the same worker fail with [`TaskError::WorkerTerminated`].
+    /// A [`crate::pool::WebWorkerPool`] recreates the worker on demand.
+    pub fn abort_and_terminate(&self) {
+        self.abort();
+        if let Some(worker) = self.worker.borrow().as_ref() {
+            worker.terminate();
+        }
+    }
+
+    /// Returns whether the task has been aborted.
+    pub fn is_aborted(&self) -> bool {
+        self.handle.is_aborted()
+    }
+}
+
+/// Wraps the future of a task so that it resolves to [`TaskError::Cancelled`]
+/// once the corresponding [`AbortHandle`] is aborted.
+/// Dropping the inner future cleans up the in-flight task.
+pub(crate) async fn abortable<R>(
+    future: impl Future<Output = Result<R, TaskError>>,
+    registration: AbortRegistration,
+) -> Result<R, TaskError> {
+    Abortable::new(future, registration)
+        .await
+        .unwrap_or(Err(TaskError::Cancelled))
+}
@@ -1,26 +1,104 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    marker::PhantomData,
+    rc::Rc,
+};
 
-use serde::{de::DeserializeOwned, Serialize};
-use tokio::sync::mpsc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::sync::{mpsc, Notify};
 use wasm_bindgen::prelude::*;
 use web_sys::{MessageChannel, MessageEvent, MessagePort};
 
 use crate::{
     convert::{from_bytes, to_bytes},
-    error::InitError,
+    error::{Closed, InitError, TrySendError},
 };
 
+/// Control messages sent over the port next to the regular messages.
+/// Regular messages are always sent as `Uint8Array`s, which lets us tell them apart.
+#[derive(Serialize, Deserialize)]
+enum Control {
+    /// The task using the channel has been cancelled.
+    Cancel,
+    /// The other side has closed the channel.
+    Close,
+    /// The other side of a bounded channel has consumed the given number of messages.
+    Credit(u32),
+}
+
+/// The state shared between a [`Channel`] and its message callback.
+struct ChannelState {
+    /// The sender for incoming messages,
+    /// which is dropped once the task is cancelled or the channel is closed.
+    sender: RefCell<Option<mpsc::UnboundedSender<JsValue>>>,
+    /// Whether the task using the channel has been cancelled.
+    cancelled: Cell<bool>,
+    /// Whether the channel has been closed by either side.
+    closed: Cell<bool>,
+    /// The capacity of a bounded channel, which is the same for both sides.
+    capacity: Option<u32>,
+    /// The number of messages that may be sent before the other side grants new credits.
+    credits: Cell<u32>,
+    /// The number of messages received since credits have last been granted.
+    consumed: Cell<u32>,
+    /// Notifies waiters once the task has been cancelled, the channel has been closed,
+    /// or new credits have been granted.
+    notify: Notify,
+}
+
+impl ChannelState {
+    /// Marks the channel as closed and lets pending receivers return `None`
+    /// once the buffer is drained.
+    fn close(&self) {
+        self.closed.set(true);
+        self.sender.borrow_mut().take();
+        self.notify.notify_waiters();
+    }
+}
+
 /// A bidirectional communication channel between the main thread and a WebWorker.
 ///
 /// Channels allow workers to send messages back to the main thread during execution,
 /// not just when returning results. This enables use cases like progress reporting,
 /// DOM manipulation requests, and other interactive patterns.
+///
+/// If the task is cancelled via [`crate::ChannelTask::cancel`], the worker can observe
+/// this through [`Channel::is_cancelled`] or [`Channel::cancelled`].
+///
+/// Either side can close the channel using [`Channel::close`].
+/// The channel of a `#[webworker_channel_fn]` is closed automatically once the function returns.
+/// Afterwards, [`Channel::recv`] returns `None` once all buffered messages have been received
+/// and sending fails.
+///
+/// A channel can be bounded using [`crate::WebWorker::run_channel_bounded`].
+/// For bounded channels, [`Channel::send_async`] waits until the other side has received
+/// enough of the previous messages, so that at most `capacity` messages are buffered,
+/// and [`Channel::try_send`] fails with [`TrySendError::Full`] instead of waiting.
+/// **[`Channel::send`] does not respect the capacity** and always sends the message.
+///
+/// Clones share the same underlying `MessagePort`, which is closed once the last clone is dropped.
 #[derive(Clone)]
 pub struct Channel {
     /// The message queue to await / incoming messages
     messages: Rc<RefCell<mpsc::UnboundedReceiver<JsValue>>>,
-    /// The internal message port to send and receive data
+    /// The state shared with the message callback
+    state: Rc<ChannelState>,
+    /// The internal message port to send and receive data, shared by all clones
+    port: Rc<PortHandle>,
+}
+
+/// Owns the message port together with its message callback.
+/// Once the last [`Channel`] clone is dropped, the callback is removed and the port is closed.
+struct PortHandle {
     port: MessagePort,
+    _callback: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl Drop for PortHandle {
+    fn drop(&mut self) {
+        self.port.set_onmessage(None);
+        self.port.close();
+    }
 }
 
 impl Channel {
@@ -34,18 +112,100 @@ impl Channel {
     }
 
     /// Handle messages received by the port and forwards them into the message stream
-    fn on_message_callback(
-        sender: mpsc::UnboundedSender<JsValue>,
-    ) -> Closure<dyn FnMut(MessageEvent)> {
+    fn on_message_callback(state: Rc<ChannelState>) -> Closure<dyn FnMut(MessageEvent)> {
         Closure::new(move |event: MessageEvent| {
-            let _ = sender.send(event.data());
+            let data = event.data();
+            if data.is_instance_of::<js_sys::Uint8Array>() {
+                if let Some(sender) = state.sender.borrow().as_ref() {
+                    let _ = sender.send(data);
+                }
+                return;
+            }
+
+            match serde_wasm_bindgen::from_value(data) {
+                Ok(Control::Cancel) => {
+                    state.cancelled.set(true);
+                    // Let pending receivers return `None` once the buffer is drained.
+                    state.sender.borrow_mut().take();
+                    state.notify.notify_waiters();
+                }
+                Ok(Control::Close) => state.close(),
+                Ok(Control::Credit(credits)) => {
+                    state.credits.set(state.credits.get() + credits);
+                    state.notify.notify_waiters();
+                }
+                Err(e) => crate::trace::trace_warn!("Received unknown message on channel: {e}"),
+            }
         })
     }
 
+    /// Returns whether the task using this channel has been cancelled.
+    ///
+    /// After cancellation, [`Channel::recv`] returns `None` once all
+    /// buffered messages have been received.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.get()
+    }
+
+    /// Waits until the task using this channel has been cancelled.
+    ///
+    /// This can be combined with other futures to stop work early.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.state.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Closes the channel for both sides.
+    ///
+    /// The other side is notified and its [`Channel::recv`] returns `None`
+    /// once all messages sent before closing have been received.
+    /// Messages that arrive after closing are discarded and sending fails.
+    pub fn close(&self) {
+        if self.is_closed() {
+            return;
+        }
+        self.post_control(Control::Close);
+        self.state.close();
+    }
+
+    /// Returns whether the channel has been closed by either side.
+    pub fn is_closed(&self) -> bool {
+        self.state.closed.get()
+    }
+
+    /// Waits until the channel has been closed by either side.
+    pub async fn closed(&self) {
+        loop {
+            let notified = self.state.notify.notified();
+            if self.is_closed() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Signals cancellation to the other end of the channel.
+    pub(crate) fn send_cancel(&self) {
+        self.post_control(Control::Cancel);
+    }
+
+    /// Posts a control message to the other end of the channel.
+    fn post_control(&self, control: Control) {
+        let msg =
+            serde_wasm_bindgen::to_value(&control).expect("Couldn't serialize control message");
+        // The other end might have already been closed.
+        let _ = self.port.port.post_message(&msg);
+    }
+
     /// Receives the next value for this receiver.
     ///
-    /// This method returns `None` if the channel has been closed and there are no remaining
-    /// messages in the channel's buffer. If there are no messages in the channel's buffer,
+    /// This method returns `None` if the channel has been closed (or the task has been cancelled)
+    /// and there are no remaining messages in the channel's buffer. If there are no messages in the channel's buffer,
     /// but the channel has not yet been closed, this method will sleep until a message is
     /// sent or the channel is closed.
     pub async fn recv<T: DeserializeOwned>(&self) -> Option<T> {
@@ -60,38 +220,285 @@ impl Channel {
         let mut messages = self.messages.borrow_mut();
         let value = messages.recv().await?;
         drop(messages);
+        self.grant_credit();
         let array = js_sys::Uint8Array::new(&value);
         Some(array.to_vec().into_boxed_slice())
     }
 
     /// Send a value to the receiver.
+    ///
+    /// Panics if the channel has been closed. Use [`Channel::try_send`] for a fallible variant.
+    ///
+    /// **This bypasses the capacity of a bounded channel**: the message is sent even if
+    /// the other side has not received the previous messages yet.
+    /// Use [`Channel::send_async`] or [`Channel::try_send`] to respect the capacity.
     pub fn send<T: Serialize>(&self, msg: &T) {
-        let bytes = to_bytes(msg);
-        self.send_bytes(&bytes);
+        self.send_bytes(&to_bytes(msg));
     }
 
     /// Send raw byte values to the receiver.
+    ///
+    /// Panics if the channel has been closed. Use [`Channel::try_send_bytes`] for a fallible variant.
+    ///
+    /// **This bypasses the capacity of a bounded channel**, see [`Channel::send`].
     pub fn send_bytes(&self, bytes: &[u8]) {
+        assert!(!self.is_closed(), "Channel is already closed");
+        self.state
+            .credits
+            .set(self.state.credits.get().saturating_sub(1));
+        self.post_bytes(bytes).expect("Channel is already closed");
+    }
+
+    /// Send a value to the receiver without waiting.
+    ///
+    /// Fails with [`TrySendError::Full`] if the channel is bounded and its capacity has been reached,
+    /// and with [`TrySendError::Closed`] if the channel has been closed.
+    pub fn try_send<T: Serialize>(&self, msg: &T) -> Result<(), TrySendError> {
+        self.try_send_bytes(&to_bytes(msg))
+    }
+
+    /// Send raw byte values to the receiver without waiting.
+    ///
+    /// See [`Channel::try_send`] for the possible errors.
+    pub fn try_send_bytes(&self, bytes: &[u8]) -> Result<(), TrySendError> {
+        if self.is_closed() {
+            return Err(Closed.into());
+        }
+        let credits = self.state.credits.get();
+        if self.state.capacity.is_some() {
+            if credits == 0 {
+                return Err(TrySendError::Full);
+            }
+            self.state.credits.set(credits - 1);
+        }
+        Ok(self.post_bytes(bytes)?)
+    }
+
+    /// Send a value to the receiver, waiting for capacity if the channel is bounded.
+    ///
+    /// Fails with [`Closed`] if the channel has been closed.
+    pub async fn send_async<T: Serialize>(&self, msg: &T) -> Result<(), Closed> {
+        self.send_bytes_async(&to_bytes(msg)).await
+    }
+
+    /// Send raw byte values to the receiver, waiting for capacity if the channel is bounded.
+    ///
+    /// Fails with [`Closed`] if the channel has been closed.
+    pub async fn send_bytes_async(&self, bytes: &[u8]) -> Result<(), Closed> {
+        loop {
+            let notified = self.state.notify.notified();
+            if self.is_closed() {
+                return Err(Closed);
+            }
+            let credits = self.state.credits.get();
+            if self.state.capacity.is_none() || credits > 0 {
+                self.state.credits.set(credits.saturating_sub(1));
+                break;
+            }
+            notified.await;
+        }
+        self.post_bytes(bytes)
+    }
+
+    /// Returns the capacity of a bounded channel, or `None` if the channel is unbounded.
+    pub fn capacity(&self) -> Option<u32> {
+        self.state.capacity
+    }
+
+    /// Lets the other side of a bounded channel send more messages
+    /// once half of its capacity has been received.
+    fn grant_credit(&self) {
+        let Some(capacity) = self.state.capacity else {
+            return;
+        };
+        let consumed = self.state.consumed.get() + 1;
+        if consumed >= (capacity / 2).max(1) {
+            self.post_control(Control::Credit(consumed));
+            self.state.consumed.set(0);
+        } else {
+            self.state.consumed.set(consumed);
+        }
+    }
+
+    /// Posts the bytes to the other end of the channel.
+    fn post_bytes(&self, bytes: &[u8]) -> Result<(), Closed> {
         let array = js_sys::Uint8Array::new_with_length(bytes.len() as u32);
         array.copy_from(bytes);
+        // The buffer is transferred instead of being copied again.
+        let transfer = js_sys::Array::of1(&array.buffer());
         self.port
-            .post_message(&array)
-            .expect("Channel is already closed");
+            .port
+            .post_message_with_transferable(&array, &transfer)
+            .map_err(|_| Closed)
     }
 }
 
 impl From<MessagePort> for Channel {
     /// Create a new Channel from a MessagePort
     fn from(port: MessagePort) -> Self {
+        Self::with_capacity(port, None)
+    }
+}
+
+impl Channel {
+    /// Create a new Channel from a MessagePort, which is bounded if a `capacity` is given.
+    /// Both ends of the `MessageChannel` need to use the same capacity.
+    #[doc(hidden)]
+    pub fn with_capacity(port: MessagePort, capacity: Option<u32>) -> Self {
+        let capacity = capacity.map(|capacity| capacity.max(1));
         let (sender, receiver) = mpsc::unbounded_channel();
+        let state = Rc::new(ChannelState {
+            sender: RefCell::new(Some(sender)),
+            cancelled: Cell::new(false),
+            closed: Cell::new(false),
+            capacity,
+            credits: Cell::new(capacity.unwrap_or(0)),
+            consumed: Cell::new(0),
+            notify: Notify::new(),
+        });
 
-        let callback_handle = Self::on_message_callback(sender);
-        port.set_onmessage(Some(callback_handle.as_ref().unchecked_ref()));
-        callback_handle.forget();
+        let callback = Self::on_message_callback(Rc::clone(&state));
+        port.set_onmessage(Some(callback.as_ref().unchecked_ref()));
 
         Self {
             messages: Rc::new(RefCell::new(receiver)),
-            port,
+            state,
+            port: Rc::new(PortHandle {
+                port,
+                _callback: callback,
+            }),
+        }
+    }
+}
+
+/// A marker for channels whose message types are chosen per call,
+/// as is the case for a plain [`Channel`].
+pub enum Untyped {}
+
+/// A channel parameter of a `#[webworker_channel_fn]`, which is either a [`Channel`]
+/// or a [`TypedChannel`].
+///
+/// `In` is the type of the messages sent to the worker,
+/// `Out` is the type of the messages sent by the worker.
+#[doc(hidden)]
+pub trait WorkerChannel: From<Channel> {
+    type In;
+    type Out;
+}
+
+impl WorkerChannel for Channel {
+    type In = Untyped;
+    type Out = Untyped;
+}
+
+impl<In, Out> WorkerChannel for TypedChannel<In, Out> {
+    type In = In;
+    type Out = Out;
+}
+
+/// A [`Channel`] with fixed message types, which are checked at compile time.
+///
+/// Workers receive messages of type `In` and send messages of type `Out`.
+/// Using a `TypedChannel<In, Out>` as the parameter of a `#[webworker_channel_fn]`
+/// makes the corresponding [`crate::ChannelTask`] send `In` and receive `Out` messages.
+///
+/// # Example
+///
+/// ```ignore
+/// #[webworker_channel_fn]
+/// pub async fn process(data: Vec<u8>, channel: TypedChannel<Continue, Progress>) -> Output {
+///     channel.send(&Progress { percent: 50 });
+///     let response: Option<Continue> = channel.recv().await;
+///     // ...
+/// }
+/// ```
+pub struct TypedChannel<In, Out> {
+    channel: Channel,
+    _phantom: PhantomData<fn(Out) -> In>,
+}
+
+impl<In, Out> Clone for TypedChannel<In, Out> {
+    fn clone(&self) -> Self {
+        Self {
+            channel: self.channel.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<In: DeserializeOwned, Out: Serialize> TypedChannel<In, Out> {
+    /// Receives the next message.
+    ///
+    /// See [`Channel::recv`] for details.
+    pub async fn recv(&self) -> Option<In> {
+        self.channel.recv().await
+    }
+
+    /// Sends a message to the other side.
+    ///
+    /// Panics if the channel has been closed. Use [`TypedChannel::try_send`] for a fallible variant.
+    /// Like [`Channel::send`], this bypasses the capacity of a bounded channel.
+    pub fn send(&self, msg: &Out) {
+        self.channel.send(msg);
+    }
+
+    /// Sends a message to the other side without waiting.
+    ///
+    /// See [`Channel::try_send`] for the possible errors.
+    pub fn try_send(&self, msg: &Out) -> Result<(), TrySendError> {
+        self.channel.try_send(msg)
+    }
+
+    /// Sends a message to the other side, waiting for capacity if the channel is bounded.
+    ///
+    /// See [`Channel::send_async`] for details.
+    pub async fn send_async(&self, msg: &Out) -> Result<(), Closed> {
+        self.channel.send_async(msg).await
+    }
+}
+
+impl<In, Out> TypedChannel<In, Out> {
+    /// Returns whether the task using this channel has been cancelled.
+    ///
+    /// See [`Channel::is_cancelled`] for details.
+    pub fn is_cancelled(&self) -> bool {
+        self.channel.is_cancelled()
+    }
+
+    /// Waits until the task using this channel has been cancelled.
+    pub async fn cancelled(&self) {
+        self.channel.cancelled().await
+    }
+
+    /// Closes the channel for both sides.
+    ///
+    /// See [`Channel::close`] for details.
+    pub fn close(&self) {
+        self.channel.close();
+    }
+
+    /// Returns whether the channel has been closed by either side.
+    pub fn is_closed(&self) -> bool {
+        self.channel.is_closed()
+    }
+
+    /// Waits until the channel has been closed by either side.
+    pub async fn closed(&self) {
+        self.channel.closed().await
+    }
+
+    /// Returns the underlying untyped [`Channel`].
+    pub fn into_inner(self) -> Channel {
+        self.channel
+    }
+}
+
+impl<In, Out> From<Channel> for TypedChannel<In, Out> {
+    /// Fixes the message types of a [`Channel`].
+    fn from(channel: Channel) -> Self {
+        Self {
+            channel,
+            _phantom: PhantomData,
         }
     }
 }
@@ -1,9 +1,21 @@
-use std::marker::PhantomData;
+use std::{cell::Cell, marker::PhantomData};
 
 use serde::{de::DeserializeOwned, Serialize};
 use tokio::sync::oneshot;
 
-use crate::{channel::Channel, convert::from_bytes};
+use crate::{
+    channel::{Channel, Untyped},
+    convert::try_from_bytes,
+    error::{Closed, TaskError, TrySendError},
+    pool::Admission,
+    webworker::PendingTask,
+};
+
+/// An internal type for the receiver of a channel task's serialized result.
+pub(crate) type ResultReceiver = oneshot::Receiver<Result<Vec<u8>, TaskError>>;
+
+/// An internal type for the pending task and the pool admission of a channel task.
+pub(crate) type TaskGuards = (Option<PendingTask>, Option<Admission>);
 
 /// A handle to a running channel task on a WebWorker.
 ///
@@ -26,54 +38,243 @@ use crate::{channel::Channel, convert::from_bytes};
 ///
 /// let result: ProcessResult = task.result().await;
 /// ```
-pub struct ChannelTask<R> {
+///
+/// If the worker function takes a [`crate::TypedChannel<In, Out>`], the task sends
+/// messages of type `In` and receives messages of type `Out`, which is checked at compile time.
+/// Otherwise, the message types are chosen per call.
+///
+/// Dropping a `ChannelTask` before its result has been received cancels the task
+/// like [`ChannelTask::cancel`], so that a worker waiting in [`Channel::recv`] returns.
+pub struct ChannelTask<R, In = Untyped, Out = Untyped> {
     channel: Channel,
-    result_rx: oneshot::Receiver<Vec<u8>>,
+    /// The receiver for the task's result, until the result has been received.
+    result_rx: Option<ResultReceiver>,
+    /// The task on the worker, if it has been posted successfully.
+    pending: Option<PendingTask>,
+    /// The task's admission to the pool, if it runs on a [`crate::WebWorkerPool`].
+    admission: Option<Admission>,
+    cancelled: Cell<bool>,
     _phantom: PhantomData<R>,
+    _messages: PhantomData<fn(In) -> Out>,
+}
+
+impl<R> ChannelTask<R> {
+    /// Receive the next deserialized message from the worker.
+    ///
+    /// Returns `None` once the channel has been closed and all messages have been received,
+    /// e.g., after the worker function has returned.
+    pub async fn recv<T: DeserializeOwned>(&self) -> Option<T> {
+        self.channel.recv().await
+    }
+
+    /// Send a serialized message to the worker.
+    ///
+    /// Panics if the channel has been closed. Use [`ChannelTask::try_send`] for a fallible variant.
+    /// Like [`Channel::send`], this bypasses the capacity of a bounded channel.
+    pub fn send<T: Serialize>(&self, msg: &T) {
+        self.channel.send(msg);
+    }
+
+    /// Send a serialized message to the worker without waiting.
+    ///
+    /// See [`Channel::try_send`] for the possible errors.
+    pub fn try_send<T: Serialize>(&self, msg: &T) -> Result<(), TrySendError> {
+        self.channel.try_send(msg)
+    }
+
+    /// Send a serialized message to the worker, waiting for capacity if the channel is bounded.
+    ///
+    /// Fails with [`Closed`] if the channel has been closed.
+    pub async fn send_async<T: Serialize>(&self, msg: &T) -> Result<(), Closed> {
+        self.channel.send_async(msg).await
+    }
+}
+
+impl<R, In: Serialize, Out: DeserializeOwned> ChannelTask<R, In, Out> {
+    /// Receive the next message from the worker.
+    ///
+    /// Returns `None` once the channel has been closed and all messages have been received,
+    /// e.g., after the worker function has returned.
+    pub async fn recv(&self) -> Option<Out> {
+        self.channel.recv().await
+    }
+
+    /// Send a message to the worker.
+    ///
+    /// Panics if the channel has been closed. Use [`ChannelTask::try_send`] for a fallible variant.
+    /// Like [`Channel::send`], this bypasses the capacity of a bounded channel.
+    pub fn send(&self, msg: &In) {
+        self.channel.send(msg);
+    }
+
+    /// Send a message to the worker without waiting.
+    ///
+    /// See [`Channel::try_send`] for the possible errors.
+    pub fn try_send(&self, msg: &In) -> Result<(), TrySendError> {
+        self.channel.try_send(msg)
+    }
+
+    /// Send a message to the worker, waiting for capacity if the channel is bounded.
+    ///
+    /// Fails with [`Closed`] if the channel has been closed.
+    pub async fn send_async(&self, msg: &In) -> Result<(), Closed> {
+        self.channel.send_async(msg).await
+    }
+}
+
+impl<R, In, Out> ChannelTask<R, In, Out> {
+    /// Cancel the task.
+    ///
+    /// The worker can observe the cancellation via [`Channel::is_cancelled`]
+    /// or [`Channel::cancelled`] and is expected to return early.
+    /// The task's result is discarded and [`ChannelTask::result_checked`]
+    /// returns [`TaskError::Cancelled`].
+    pub fn cancel(&self) {
+        if self.cancelled.replace(true) {
+            return;
+        }
+        self.channel.send_cancel();
+        if let Some(pending) = &self.pending {
+            pending.cancel();
+        }
+    }
+
+    /// Cancel the task and terminate the worker it is running on.
+    ///
+    /// This is useful if the worker function does not check for cancellation.
+    /// All other tasks on the same worker fail with [`TaskError::WorkerTerminated`].
+    /// A [`crate::pool::WebWorkerPool`] recreates the worker on demand.
+    pub fn cancel_and_terminate(&self) {
+        self.cancel();
+        if let Some(pending) = &self.pending {
+            pending.worker().terminate();
+        }
+    }
+
+    /// Returns whether the task has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
 }
 
-impl<R: DeserializeOwned> ChannelTask<R> {
+impl<R: DeserializeOwned, In, Out> ChannelTask<R, In, Out> {
     /// Create a new `ChannelTask` from a channel and a result receiver.
-    #[doc(hidden)]
-    pub fn new(channel: Channel, result_rx: oneshot::Receiver<Vec<u8>>) -> Self {
+    pub(crate) fn new(
+        channel: Channel,
+        result_rx: ResultReceiver,
+        pending: Option<PendingTask>,
+    ) -> Self {
         Self {
             channel,
-            result_rx,
+            result_rx: Some(result_rx),
+            pending,
+            admission: None,
+            cancelled: Cell::new(false),
             _phantom: PhantomData,
+            _messages: PhantomData,
         }
     }
 
-    /// Receive the next deserialized message from the worker.
-    ///
-    /// Returns `None` if the channel's sender side has been dropped
-    /// (i.e., the worker has finished and closed the channel).
-    pub async fn recv<T: DeserializeOwned>(&self) -> Option<T> {
-        self.channel.recv().await
+    /// Holds the task's admission to the pool until the task has finished.
+    pub(crate) fn with_admission(mut self, admission: Admission) -> Self {
+        self.admission = Some(admission);
+        self
+    }
+
+    /// Splits the task into its channel, the receiver for the result,
+    /// and the guards that need to live as long as the task.
+    pub(crate) fn into_parts(mut self) -> (Channel, ResultReceiver, TaskGuards) {
+        let result_rx = self
+            .result_rx
+            .take()
+            .expect("The task's result has already been received");
+        (
+            self.channel.clone(),
+            result_rx,
+            (self.pending.take(), self.admission.take()),
+        )
     }
 
     /// Receive raw bytes from the worker.
     ///
-    /// Returns `None` if the channel's sender side has been dropped.
+    /// Returns `None` once the channel has been closed and all messages have been received.
     pub async fn recv_bytes(&self) -> Option<Box<[u8]>> {
         self.channel.recv_bytes().await
     }
 
-    /// Send a serialized message to the worker.
-    pub fn send<T: Serialize>(&self, msg: &T) {
-        self.channel.send(msg);
-    }
-
     /// Send raw bytes to the worker.
+    ///
+    /// Panics if the channel has been closed.
+    /// Use [`ChannelTask::try_send_bytes`] for a fallible variant.
+    /// Like [`Channel::send`], this bypasses the capacity of a bounded channel.
     pub fn send_bytes(&self, bytes: &[u8]) {
         self.channel.send_bytes(bytes);
     }
 
+    /// Send raw bytes to the worker without waiting.
+    ///
+    /// See [`Channel::try_send`] for the possible errors.
+    pub fn try_send_bytes(&self, bytes: &[u8]) -> Result<(), TrySendError> {
+        self.channel.try_send_bytes(bytes)
+    }
+
+    /// Returns the capacity of a bounded channel, or `None` if the channel is unbounded.
+    pub fn capacity(&self) -> Option<u32> {
+        self.channel.capacity()
+    }
+
+    /// Close the channel for both sides.
+    ///
+    /// The worker's [`Channel::recv`] returns `None` once all messages sent before
+    /// closing have been received. The task itself keeps running.
+    pub fn close(&self) {
+        self.channel.close();
+    }
+
+    /// Returns whether the channel has been closed by either side.
+    ///
+    /// The worker closes the channel once its function has returned.
+    pub fn is_closed(&self) -> bool {
+        self.channel.is_closed()
+    }
+
+    /// Waits until the channel has been closed by either side.
+    pub async fn closed(&self) {
+        self.channel.closed().await
+    }
+
     /// Await the task's final result, consuming the `ChannelTask`.
+    ///
+    /// Panics if the task could not be completed.
+    /// Use [`ChannelTask::result_checked`] for a fallible variant.
     pub async fn result(self) -> R {
-        let bytes = self
+        self.result_checked().await.expect("WebWorker task failed")
+    }
+
+    /// Await the task's final result, consuming the `ChannelTask`.
+    ///
+    /// In contrast to [`ChannelTask::result`], this returns a [`TaskError`]
+    /// if the task could not be completed.
+    pub async fn result_checked(mut self) -> Result<R, TaskError> {
+        if self.is_cancelled() {
+            return Err(TaskError::Cancelled);
+        }
+        let result_rx = self
             .result_rx
-            .await
-            .expect("WebWorker result sender dropped");
-        from_bytes(&bytes)
+            .as_mut()
+            .expect("The task's result has already been received");
+        let result = result_rx.await;
+        self.result_rx = None;
+        let bytes = result.map_err(|_| TaskError::WorkerTerminated)??;
+        try_from_bytes(&bytes)
+    }
+}
+
+impl<R, In, Out> Drop for ChannelTask<R, In, Out> {
+    fn drop(&mut self) {
+        // Let the worker's receive loop end instead of waiting for messages forever.
+        if self.result_rx.is_some() {
+            self.cancel();
+        }
     }
 }
@@ -1,46 +1,178 @@
 use serde::{Deserialize, Serialize};
 
-/// This wrapper function encapsulates our internal serialization format.
-/// It is used internally to prepare values before sending them to a worker
-/// or back to the main thread via `postMessage`.
-#[cfg(feature = "codec-postcard")]
-pub fn to_bytes<T: Serialize>(value: &T) -> Box<[u8]> {
-    postcard::to_allocvec(value)
-        .expect("WebWorker serialization failed")
-        .into()
+use crate::error::TaskError;
+
+/// A codec defines the serialization format used to exchange values with a worker.
+///
+/// The codec is part of the type of a [`crate::func::WebWorkerFn`] and can be chosen per function
+/// using `#[webworker_fn(codec = ...)]`. This ensures that the main thread and the worker
+/// always agree on the format. Functions without an explicit codec use [`DefaultCodec`].
+///
+/// The built-in codecs are enabled via cargo features:
+/// - [`PostcardCodec`] (`codec-postcard`, enabled by default)
+/// - [`PotCodec`] (`codec-pot`)
+/// - [`BincodeCodec`] (`codec-bincode`)
+/// - [`JsonCodec`] (`codec-json`)
+///
+/// Custom codecs can be used by implementing this trait.
+pub trait Codec: 'static {
+    /// Serializes a value, returning a [`TaskError::Serialization`] on failure.
+    fn encode<T: Serialize>(value: &T) -> Result<Box<[u8]>, TaskError>;
+
+    /// Deserializes a value, returning a [`TaskError::Deserialization`] on failure.
+    fn decode<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, TaskError>;
 }
 
-/// This wrapper function encapsulates our internal serialization format.
-/// It is used internally to prepare values after receiving them from a worker
-/// or the main thread via `postMessage`.
+/// The codec used for functions that do not specify one, as well as for [`crate::Channel`] messages.
+///
+/// If multiple codec features are enabled, the first one of
+/// [`PostcardCodec`], [`PotCodec`], [`BincodeCodec`] and [`JsonCodec`] is used.
 #[cfg(feature = "codec-postcard")]
-pub fn from_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> T {
-    postcard::from_bytes(bytes).expect("WebWorker deserialization failed")
-}
+pub type DefaultCodec = PostcardCodec;
 
+/// The codec used for functions that do not specify one, as well as for [`crate::Channel`] messages.
+///
+/// If multiple codec features are enabled, the first one of
+/// [`PostcardCodec`], [`PotCodec`], [`BincodeCodec`] and [`JsonCodec`] is used.
 #[cfg(all(feature = "codec-pot", not(feature = "codec-postcard")))]
-const POT_CONFIG: pot::Config = pot::Config::new().compatibility(pot::Compatibility::V4);
+pub type DefaultCodec = PotCodec;
+
+/// The codec used for functions that do not specify one, as well as for [`crate::Channel`] messages.
+///
+/// If multiple codec features are enabled, the first one of
+/// [`PostcardCodec`], [`PotCodec`], [`BincodeCodec`] and [`JsonCodec`] is used.
+#[cfg(all(
+    feature = "codec-bincode",
+    not(any(feature = "codec-postcard", feature = "codec-pot"))
+))]
+pub type DefaultCodec = BincodeCodec;
+
+/// The codec used for functions that do not specify one, as well as for [`crate::Channel`] messages.
+///
+/// If multiple codec features are enabled, the first one of
+/// [`PostcardCodec`], [`PotCodec`], [`BincodeCodec`] and [`JsonCodec`] is used.
+#[cfg(all(
+    feature = "codec-json",
+    not(any(
+        feature = "codec-postcard",
+        feature = "codec-pot",
+        feature = "codec-bincode"
+    ))
+))]
+pub type DefaultCodec = JsonCodec;
+
+#[cfg(not(any(
+    feature = "codec-postcard",
+    feature = "codec-pot",
+    feature = "codec-bincode",
+    feature = "codec-json"
+)))]
+compile_error!(
+    "No codec selected. Enable `codec-postcard` (default), `codec-pot`, `codec-bincode` or `codec-json`."
+);
 
 /// This wrapper function encapsulates our internal serialization format.
 /// It is used internally to prepare values before sending them to a worker
 /// or back to the main thread via `postMessage`.
-#[cfg(all(feature = "codec-pot", not(feature = "codec-postcard")))]
+///
+/// Panics if the value cannot be serialized. Use [`try_to_bytes`] for a fallible variant.
 pub fn to_bytes<T: Serialize>(value: &T) -> Box<[u8]> {
-    POT_CONFIG
-        .serialize(value)
-        .expect("WebWorker serialization failed")
-        .into()
+    try_to_bytes(value).expect("WebWorker serialization failed")
 }
 
 /// This wrapper function encapsulates our internal serialization format.
 /// It is used internally to prepare values after receiving them from a worker
 /// or the main thread via `postMessage`.
-#[cfg(all(feature = "codec-pot", not(feature = "codec-postcard")))]
+///
+/// Panics if the value cannot be deserialized. Use [`try_from_bytes`] for a fallible variant.
 pub fn from_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> T {
-    POT_CONFIG
-        .deserialize(bytes)
-        .expect("WebWorker deserialization failed")
+    try_from_bytes(bytes).expect("WebWorker deserialization failed")
 }
 
-#[cfg(not(any(feature = "codec-postcard", feature = "codec-pot")))]
-compile_error!("No codec selected. Enable `codec-postcard` (default) or `codec-pot`.");
+/// Fallible variant of [`to_bytes`], which returns a [`TaskError::Serialization`]
+/// instead of panicking.
+pub fn try_to_bytes<T: Serialize>(value: &T) -> Result<Box<[u8]>, TaskError> {
+    DefaultCodec::encode(value)
+}
+
+/// Fallible variant of [`from_bytes`], which returns a [`TaskError::Deserialization`]
+/// instead of panicking.
+pub fn try_from_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, TaskError> {
+    DefaultCodec::decode(bytes)
+}
+
+/// A compact binary codec based on [`postcard`].
+#[cfg(feature = "codec-postcard")]
+pub struct PostcardCodec;
+
+#[cfg(feature = "codec-postcard")]
+impl Codec for PostcardCodec {
+    fn encode<T: Serialize>(value: &T) -> Result<Box<[u8]>, TaskError> {
+        postcard::to_allocvec(value)
+            .map(Into::into)
+            .map_err(|e| TaskError::Serialization(e.to_string()))
+    }
+
+    fn decode<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, TaskError> {
+        postcard::from_bytes(bytes).map_err(|e| TaskError::Deserialization(e.to_string()))
+    }
+}
+
+#[cfg(feature = "codec-pot")]
+const POT_CONFIG: pot::Config = pot::Config::new().compatibility(pot::Compatibility::V4);
+
+/// A self-describing binary codec based on [`pot`].
+#[cfg(feature = "codec-pot")]
+pub struct PotCodec;
+
+#[cfg(feature = "codec-pot")]
+impl Codec for PotCodec {
+    fn encode<T: Serialize>(value: &T) -> Result<Box<[u8]>, TaskError> {
+        POT_CONFIG
+            .serialize(value)
+            .map(Into::into)
+            .map_err(|e| TaskError::Serialization(e.to_string()))
+    }
+
+    fn decode<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, TaskError> {
+        POT_CONFIG
+            .deserialize(bytes)
+            .map_err(|e| TaskError::Deserialization(e.to_string()))
+    }
+}
+
+/// A compact binary codec based on [`bincode`] with its standard configuration.
+#[cfg(feature = "codec-bincode")]
+pub struct BincodeCodec;
+
+#[cfg(feature = "codec-bincode")]
+impl Codec for BincodeCodec {
+    fn encode<T: Serialize>(value: &T) -> Result<Box<[u8]>, TaskError> {
+        bincode::serde::encode_to_vec(value, bincode::config::standard())
+            .map(Into::into)
+            .map_err(|e| TaskError::Serialization(e.to_string()))
+    }
+
+    fn decode<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, TaskError> {
+        bincode::serde::borrow_decode_from_slice(bytes, bincode::config::standard())
+            .map(|(value, _)| value)
+            .map_err(|e| TaskError::Deserialization(e.to_string()))
+    }
+}
+
+/// A human-readable codec based on [`serde_json`], which is mostly useful for debugging.
+#[cfg(feature = "codec-json")]
+pub struct JsonCodec;
+
+#[cfg(feature = "codec-json")]
+impl Codec for JsonCodec {
+    fn encode<T: Serialize>(value: &T) -> Result<Box<[u8]>, TaskError> {
+        serde_json::to_vec(value)
+            .map(Into::into)
+            .map_err(|e| TaskError::Serialization(e.to_string()))
+    }
+
+    fn decode<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, TaskError> {
+        serde_json::from_slice(bytes).map_err(|e| TaskError::Deserialization(e.to_string()))
+    }
+}
@@ -3,11 +3,33 @@ use thiserror::Error;
 
 /// This error is returned when a web worker has been configured with a
 /// maximum number of tasks to be queued and one of the `try_run` methods
-/// is called.
+/// is called. These methods wrap it in [`TryRunError::Full`].
 #[derive(Debug, Error)]
 #[error("WebWorker capacity reached")]
 pub struct Full;
 
+/// This error is returned by [`crate::Channel::send_async`] and the related methods
+/// if the channel has been closed by either side.
+/// The `try_send` methods wrap it in [`TrySendError::Closed`].
+#[derive(Debug, Error)]
+#[error("Channel is closed")]
+pub struct Closed;
+
+/// This error is returned by [`crate::Channel::try_send`] and the related methods.
+/// It distinguishes a bounded channel that is currently full, which may be retried later,
+/// from a channel that has been closed.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TrySendError {
+    /// The channel is bounded and the other side has not yet received
+    /// enough of the previous messages.
+    #[error("Channel capacity reached")]
+    Full,
+    /// The channel has been closed by either side.
+    #[error(transparent)]
+    Closed(#[from] Closed),
+}
+
 /// This error is returned during the creation of a new web worker.
 /// It covers generic errors in the actual creation and import errors
 /// during the initialization.
@@ -24,4 +46,83 @@ pub enum InitError {
     /// This error covers errors during the `new MessageChannel()` command.
     #[error("Channel creation error: {0:?}")]
     ChannelCreation(JsValue),
+    /// The init function set via [`crate::pool::WorkerPoolOptions::set_init_fn`]
+    /// panicked or has not been exported to the worker.
+    #[error("WebWorker init function failed: {0}")]
+    InitFunction(String),
+    /// The [`crate::WebWorkerPool`] has been shut down and does not start new workers.
+    #[error("WebWorkerPool has been shut down")]
+    PoolShutdown,
+}
+
+/// This error is returned by the checked task functions, such as
+/// [`crate::WebWorker::run_checked`], if a task could not be completed.
+/// It allows callers to recover from a single failed task instead of
+/// bringing down the main thread.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TaskError {
+    /// The function has not been exported to the worker.
+    /// This should never be the case if the [`crate::func::WebWorkerFn`]
+    /// was constructed using the [`crate::webworker!`] macro.
+    #[error("Function `{0}` is not exported by the WebWorker")]
+    FunctionNotExported(&'static str),
+    /// The worker has been terminated before the task could be completed.
+    #[error("WebWorker terminated")]
+    WorkerTerminated,
+    /// The task has been cancelled, e.g., via [`crate::AbortHandle::abort`].
+    #[error("WebWorker task cancelled")]
+    Cancelled,
+    /// The task did not complete within the given timeout.
+    /// The worker has been terminated to stop the computation.
+    #[error("WebWorker task timed out")]
+    Timeout,
+    /// The argument or result could not be serialized.
+    #[error("WebWorker serialization failed: {0}")]
+    Serialization(String),
+    /// The argument or result could not be deserialized.
+    #[error("WebWorker deserialization failed: {0}")]
+    Deserialization(String),
+    /// The function panicked inside the worker.
+    #[error(
+        "WebWorker panicked at {}: {message}",
+        .location.as_deref().unwrap_or("unknown location")
+    )]
+    Panic {
+        /// The panic message.
+        message: String,
+        /// The source location of the panic, if known.
+        location: Option<String>,
+    },
+    /// A worker of a [`crate::WebWorkerPool`] needed to be (re)created
+    /// to run the task, but the creation failed.
+    #[error("WebWorker creation failed: {0}")]
+    WorkerCreation(#[from] InitError),
+    /// The [`crate::WebWorkerPool`] has been shut down and does not accept new tasks.
+    #[error("WebWorkerPool has been shut down")]
+    PoolShutdown,
+    /// The worker index passed to [`crate::WebWorkerPool::run_on`]
+    /// is not smaller than the number of workers.
+    #[error("WebWorker index {0} out of range")]
+    InvalidWorker(usize),
+    /// The argument could not be sent to the worker, e.g., because its buffer
+    /// is a view on wasm memory or has already been detached by an earlier transfer.
+    /// The worker is still usable.
+    #[error("WebWorker transfer failed: {0}")]
+    TransferFailed(String),
+}
+
+/// This error is returned by the `try_run` methods, such as [`crate::WebWorkerPool::try_run`].
+/// It distinguishes a task that has been rejected because of the task limit,
+/// which may be retried later, from a task that has failed.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TryRunError {
+    /// The task has been rejected because the task limit has been reached.
+    #[error(transparent)]
+    Full(#[from] Full),
+    /// The task could not be completed, see [`TaskError`].
+    /// If the pool has been shut down, this is [`TaskError::PoolShutdown`].
+    #[error(transparent)]
+    Task(#[from] TaskError),
 }
@@ -1,52 +1,131 @@
 use std::marker::PhantomData;
 
-use futures::future::LocalBoxFuture;
+use futures::{future::LocalBoxFuture, Stream};
 
-use crate::Channel;
+use crate::{
+    channel::{Untyped, WorkerChannel},
+    convert::{Codec, DefaultCodec},
+    Channel,
+};
 
-/// This struct describes a simple synchronous function to be called by the worker.
+/// This struct describes a function to be called by the worker, which can be synchronous or `async`.
 /// It ensures type safety when constructed using the [`crate::webworker!`] macro.
 ///
-/// For async functions with channel support, use [`WebWorkerChannelFn`] instead.
-pub struct WebWorkerFn<T, R> {
+/// The [`Codec`] `C` determines how the argument and the result are serialized.
+/// It is chosen using `#[webworker_fn(codec = ...)]` and defaults to [`DefaultCodec`].
+///
+/// For functions that need to communicate with the main thread while running,
+/// use [`WebWorkerChannelFn`] instead.
+pub struct WebWorkerFn<T, R, C = DefaultCodec> {
     /// The name of the original function.
     /// The worker will automatically add the `__webworker_` prefix.
     pub(crate) name: &'static str,
     /// The original function, which can be used as a fallback.
     #[cfg_attr(not(feature = "iter-ext"), allow(dead_code))]
-    pub(crate) func: fn(T) -> R,
+    pub(crate) func: Fallback<T, R>,
+    /// Phantom data for the codec type.
+    _codec: PhantomData<fn() -> C>,
 }
 
-impl<T, R> Clone for WebWorkerFn<T, R> {
+impl<T, R, C> Clone for WebWorkerFn<T, R, C> {
     fn clone(&self) -> Self {
         *self
     }
 }
 
-impl<T, R> Copy for WebWorkerFn<T, R> {}
+impl<T, R, C> Copy for WebWorkerFn<T, R, C> {}
+
+/// The original function of a [`WebWorkerFn`], which is either synchronous or `async`.
+pub(crate) enum Fallback<T, R> {
+    Sync(fn(T) -> R),
+    Async(fn(T) -> LocalBoxFuture<'static, R>),
+}
+
+impl<T, R> Clone for Fallback<T, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, R> Copy for Fallback<T, R> {}
+
+impl<T, R> Fallback<T, R> {
+    /// Calls the original function on the current thread.
+    #[cfg_attr(not(feature = "iter-ext"), allow(dead_code))]
+    pub(crate) async fn call(self, arg: T) -> R {
+        match self {
+            Fallback::Sync(f) => f(arg),
+            Fallback::Async(f) => f(arg).await,
+        }
+    }
+}
 
 impl<T, R> WebWorkerFn<T, R> {
-    /// Manually creates a [`WebWorkerFn`] object.
+    /// Manually creates a [`WebWorkerFn`] object using the [`DefaultCodec`].
     /// This function should be avoided in most cases as it does not guarantee that the function
     /// has the right type or is exposed to the worker.
     ///
     /// Instead use the [`crate::webworker!`] macro to create an instance of this type.
     pub fn new_unchecked(func_name: &'static str, f: fn(T) -> R) -> Self {
+        Self::with_codec_unchecked(func_name, f)
+    }
+}
+
+impl<T, R, C: Codec> WebWorkerFn<T, R, C> {
+    /// Manually creates a [`WebWorkerFn`] object using the given [`Codec`].
+    /// This function should be avoided in most cases as it does not guarantee that the function
+    /// has the right type, uses the same codec, or is exposed to the worker.
+    ///
+    /// Instead use the [`crate::webworker!`] macro to create an instance of this type.
+    pub fn with_codec_unchecked(func_name: &'static str, f: fn(T) -> R) -> Self {
         Self {
             name: func_name,
-            func: f,
+            func: Fallback::Sync(f),
+            _codec: PhantomData,
+        }
+    }
+
+    /// Manually creates a [`WebWorkerFn`] object for an `async` function using the given [`Codec`].
+    /// This function should be avoided in most cases as it does not guarantee that the function
+    /// has the right type, uses the same codec, or is exposed to the worker.
+    ///
+    /// Instead use the [`crate::webworker!`] macro to create an instance of this type.
+    pub fn with_codec_async_unchecked(
+        func_name: &'static str,
+        f: fn(T) -> LocalBoxFuture<'static, R>,
+    ) -> Self {
+        Self {
+            name: func_name,
+            func: Fallback::Async(f),
+            _codec: PhantomData,
         }
     }
 }
 
+/// An init function together with its serialized argument,
+/// which each worker of a pool runs once after loading the module.
+/// It is set via [`crate::pool::WorkerPoolOptions::set_init_fn`].
+#[derive(Clone)]
+pub(crate) struct WorkerInit {
+    /// The name of the original function.
+    pub(crate) func_name: &'static str,
+    /// The argument serialized with the function's [`Codec`].
+    pub(crate) arg: Box<[u8]>,
+}
+
 /// This struct describes an async function with channel support to be called by the worker.
 /// It ensures type safety when constructed using the [`crate::webworker_channel!`] macro.
 ///
 /// The channel allows bidirectional communication between the worker and the main thread
 /// during function execution, enabling use cases like progress reporting and interactive workflows.
 ///
-/// For simple synchronous functions, use [`WebWorkerFn`] instead.
-pub struct WebWorkerChannelFn<T, R> {
+/// If the function takes a [`crate::TypedChannel<In, Out>`], the message types `In` and `Out`
+/// carry over to the [`crate::ChannelTask`]. For a plain [`Channel`], both are [`Untyped`].
+///
+/// The argument, the result, and the messages are always serialized using [`DefaultCodec`].
+///
+/// For functions that do not need a channel, use [`WebWorkerFn`] instead.
+pub struct WebWorkerChannelFn<T, R, In = Untyped, Out = Untyped> {
     /// The name of the original function.
     /// The worker will automatically add the `__webworker_channel_` prefix.
     pub(crate) name: &'static str,
@@ -56,15 +135,17 @@ pub struct WebWorkerChannelFn<T, R> {
     pub(crate) func: fn(T, Channel) -> LocalBoxFuture<'static, R>,
     /// Phantom data for the input type (needed since T isn't used directly in fields).
     pub(crate) _phantom: PhantomData<fn(T) -> R>,
+    /// Phantom data for the message types.
+    pub(crate) _messages: PhantomData<fn(In) -> Out>,
 }
 
-impl<T, R> Clone for WebWorkerChannelFn<T, R> {
+impl<T, R, In, Out> Clone for WebWorkerChannelFn<T, R, In, Out> {
     fn clone(&self) -> Self {
         *self
     }
 }
 
-impl<T, R> Copy for WebWorkerChannelFn<T, R> {}
+impl<T, R, In, Out> Copy for WebWorkerChannelFn<T, R, In, Out> {}
 
 impl<T, R> WebWorkerChannelFn<T, R> {
     /// Manually creates a [`WebWorkerChannelFn`] object.
@@ -80,6 +161,66 @@ impl<T, R> WebWorkerChannelFn<T, R> {
             name: func_name,
             func: f,
             _phantom: PhantomData,
+            _messages: PhantomData,
+        }
+    }
+}
+
+impl<T, R, In, Out> WebWorkerChannelFn<T, R, In, Out> {
+    /// Manually creates a [`WebWorkerChannelFn`] object for a function taking the channel type `Ch`,
+    /// e.g., a [`crate::TypedChannel<In, Out>`].
+    /// This function should be avoided in most cases as it does not guarantee that the function
+    /// has the right type or is exposed to the worker.
+    ///
+    /// Instead use the [`crate::webworker_channel!`] macro to create an instance of this type.
+    pub fn with_channel_unchecked<Ch>(
+        func_name: &'static str,
+        f: fn(T, Channel) -> LocalBoxFuture<'static, R>,
+    ) -> Self
+    where
+        Ch: WorkerChannel<In = In, Out = Out>,
+    {
+        Self {
+            name: func_name,
+            func: f,
+            _phantom: PhantomData,
+            _messages: PhantomData,
+        }
+    }
+}
+
+/// This struct describes a function producing a stream of items on the worker.
+/// It ensures type safety when constructed using the [`crate::webworker_stream!`] macro.
+///
+/// The type `R` is the type of the stream's items.
+/// The argument and the items are always serialized using [`DefaultCodec`].
+pub struct WebWorkerStreamFn<T, R> {
+    /// The name of the original function.
+    /// The worker will automatically add the `__webworker_channel_` prefix.
+    pub(crate) name: &'static str,
+    /// Phantom data for the input and item types.
+    _phantom: PhantomData<fn(T) -> R>,
+}
+
+impl<T, R> Clone for WebWorkerStreamFn<T, R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, R> Copy for WebWorkerStreamFn<T, R> {}
+
+impl<T, R> WebWorkerStreamFn<T, R> {
+    /// Manually creates a [`WebWorkerStreamFn`] object.
+    /// The function `f` is only used to infer the argument and item types.
+    /// This function should be avoided in most cases as it does not guarantee that the function
+    /// is exposed to the worker.
+    ///
+    /// Instead use the [`crate::webworker_stream!`] macro to create an instance of this type.
+    pub fn new_unchecked<S: Stream<Item = R>>(func_name: &'static str, _f: fn(T) -> S) -> Self {
+        Self {
+            name: func_name,
+            _phantom: PhantomData,
         }
     }
 }
@@ -87,6 +228,10 @@ impl<T, R> WebWorkerChannelFn<T, R> {
 /// This macro safely instantiates a [`WebWorkerFn`] instance to be passed to a [`crate::WebWorker`].
 /// It ensures that the function is exposed via the `#[webworker_fn]` procedural macro.
 ///
+/// For functions with zero or multiple parameters, the [`WebWorkerFn`] takes
+/// a tuple of all arguments, e.g., `WebWorkerFn<(u32, u32), u32>` for `fn add(a: u32, b: u32) -> u32`.
+/// For `async` functions, the [`WebWorkerFn`] uses the output of the future as its result type.
+///
 /// Example:
 /// ```no_run
 /// # use serde::{Serialize, Deserialize};
@@ -108,7 +253,7 @@ impl<T, R> WebWorkerChannelFn<T, R> {
 macro_rules! webworker {
     ($name:ident) => {{
         let _ = $name::__WEBWORKER;
-        $crate::func::WebWorkerFn::new_unchecked(stringify!($name), $name)
+        $name::__new()
     }};
 }
 
@@ -127,12 +272,33 @@ macro_rules! webworker {
 ///
 /// let func: WebWorkerChannelFn<Vec<u8>, Result<Output, Error>> = webworker_channel!(process_with_progress);
 /// ```
+///
+/// For a function taking a [`crate::TypedChannel<In, Out>`], the macro returns a
+/// `WebWorkerChannelFn<T, R, In, Out>`.
 #[macro_export]
 macro_rules! webworker_channel {
     ($name:ident) => {{
         let _ = $name::__WEBWORKER_CHANNEL;
-        $crate::func::WebWorkerChannelFn::new_unchecked(stringify!($name), |a, c| {
-            Box::pin($name(a, c))
-        })
+        $name::__new()
+    }};
+}
+
+/// This macro safely instantiates a [`WebWorkerStreamFn`] instance to be passed to a [`crate::WebWorker`].
+/// It ensures that the function is exposed via the `#[webworker_stream_fn]` procedural macro.
+///
+/// Example:
+/// ```ignore
+/// #[webworker_stream_fn]
+/// pub fn count_to(n: u32) -> impl Stream<Item = u32> {
+///     futures::stream::iter(1..=n)
+/// }
+///
+/// let func: WebWorkerStreamFn<u32, u32> = webworker_stream!(count_to);
+/// ```
+#[macro_export]
+macro_rules! webworker_stream {
+    ($name:ident) => {{
+        let _ = $name::__WEBWORKER_STREAM;
+        $crate::func::WebWorkerStreamFn::new_unchecked(stringify!($name), $name)
     }};
 }
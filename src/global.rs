@@ -1,10 +1,26 @@
-use send_wrapper::SendWrapper;
+use std::{cell::RefCell, rc::Rc};
+
 use tokio::sync::OnceCell;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue, UnwrapThrowExt};
 
-use crate::pool::{WebWorkerPool, WorkerPoolOptions};
+use crate::{
+    observer::JsPoolObserver,
+    pool::{PoolStats, WebWorkerPool, WorkerPoolOptions},
+};
+
+/// The cell holding a global worker pool.
+/// The pool is reference counted, so that it is freed after [`reset_worker_pool`]
+/// once all handles returned by [`worker_pool`] have been dropped.
+type PoolCell = Rc<OnceCell<Rc<WebWorkerPool>>>;
+
+thread_local! {
+    static WORKER_POOL: RefCell<PoolCell> = RefCell::default();
+}
 
-static WORKER_POOL: OnceCell<SendWrapper<WebWorkerPool>> = OnceCell::const_new();
+/// Returns the cell of the current global worker pool.
+fn pool_cell() -> PoolCell {
+    WORKER_POOL.with(|pool| Rc::clone(&pool.borrow()))
+}
 
 /// Error returned when [`init_worker_pool`] is called after the worker pool has already been initialized.
 #[derive(Debug, Clone, Copy)]
@@ -50,12 +66,14 @@ impl From<AlreadyInitialized> for JsValue {
 /// ```
 #[wasm_bindgen(js_name = initWorkerPool)]
 pub async fn init_worker_pool(options: WorkerPoolOptions) -> Result<(), AlreadyInitialized> {
-    let pool = SendWrapper::new(
-        WebWorkerPool::with_options(options)
-            .await
-            .expect_throw("Couldn't instantiate worker pool"),
-    );
-    WORKER_POOL.set(pool).map_err(|_| AlreadyInitialized)
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect_throw("Couldn't instantiate worker pool");
+    let cell = pool_cell();
+    if cell.initialized() {
+        return Err(AlreadyInitialized);
+    }
+    cell.set(Rc::new(pool)).map_err(|_| AlreadyInitialized)
 }
 
 /// JavaScript-accessible function to initialize an optimized worker pool globally.
@@ -80,19 +98,75 @@ pub async fn init_optimized_worker_pool() -> Result<(), AlreadyInitialized> {
 /// this function will initialize the worker pool prior to returning it.
 ///
 /// It will use the options provided by [`WorkerPoolOptions::default()`].
-pub async fn worker_pool() -> &'static WebWorkerPool {
-    WORKER_POOL
+///
+/// The returned handle keeps the pool alive, even after [`reset_worker_pool`].
+pub async fn worker_pool() -> Rc<WebWorkerPool> {
+    let cell = pool_cell();
+    let pool = cell
         .get_or_init(|| async {
-            SendWrapper::new(
-                WebWorkerPool::with_options(WorkerPoolOptions::default())
-                    .await
-                    .expect_throw("Couldn't instantiate worker pool"),
-            )
+            let pool = WebWorkerPool::with_options(WorkerPoolOptions::default())
+                .await
+                .expect_throw("Couldn't instantiate worker pool");
+            Rc::new(pool)
         })
-        .await
+        .await;
+    Rc::clone(pool)
 }
 
 /// This function checks if the worker pool has been initialized.
 pub fn has_worker_pool() -> bool {
-    WORKER_POOL.initialized()
+    pool_cell().initialized()
+}
+
+/// This function shuts down the global worker pool as described in [`WebWorkerPool::shutdown`],
+/// so that it can be configured anew via [`init_worker_pool`] or is recreated on next use.
+/// This is useful for tests and for applications that reconfigure the pool at runtime.
+///
+/// Handles to the previous pool remain valid, but it does not accept new tasks anymore.
+/// The previous pool is freed once all of its handles have been dropped.
+///
+/// This function can also be called from JavaScript:
+/// ```js
+/// await resetWorkerPool();
+/// await initWorkerPool(options);
+/// ```
+#[wasm_bindgen(js_name = resetWorkerPool)]
+pub async fn reset_worker_pool() {
+    let cell = WORKER_POOL.with(|pool| pool.replace(PoolCell::default()));
+    if let Some(pool) = cell.get() {
+        pool.shutdown().await;
+    }
+}
+
+/// This function returns a snapshot of the global worker pool's state and statistics,
+/// as described in [`WebWorkerPool::stats`].
+/// If the worker pool has not been initialized yet, it is initialized first.
+///
+/// This function can also be called from JavaScript, e.g., to poll it from a dashboard:
+/// ```js
+/// let stats = await workerPoolStats();
+/// console.log(`${stats.queued_tasks} queued, ${stats.in_flight_tasks} running`);
+/// ```
+#[wasm_bindgen(js_name = workerPoolStats)]
+pub async fn worker_pool_stats() -> PoolStats {
+    worker_pool().await.stats()
+}
+
+/// This function sets a JavaScript object as the observer of the global worker pool,
+/// as described in [`WebWorkerPool::set_observer`] and [`JsPoolObserver`].
+/// Passing `null` or `undefined` removes the observer.
+/// If the worker pool has not been initialized yet, it is initialized first.
+///
+/// This function can also be called from JavaScript:
+/// ```js
+/// await setWorkerPoolObserver({
+///     onTaskStarted: (event) => performance.mark(`task ${event.taskId} started`),
+///     onTaskCompleted: (event) => console.log(event.funcName, event.durationMs),
+/// });
+/// ```
+#[wasm_bindgen(js_name = setWorkerPoolObserver)]
+pub async fn set_worker_pool_observer(observer: JsValue) {
+    let observer = (!observer.is_null() && !observer.is_undefined())
+        .then(|| Rc::new(JsPoolObserver::new(observer)) as _);
+    worker_pool().await.set_observer(observer);
 }
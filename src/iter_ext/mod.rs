@@ -2,8 +2,9 @@ use std::borrow::Borrow;
 
 use futures::future::join_all;
 use serde::{Deserialize, Serialize};
+use wasm_bindgen::UnwrapThrowExt;
 
-use crate::{func::WebWorkerFn, global::has_worker_pool, worker_pool};
+use crate::{convert::Codec, func::WebWorkerFn, global::has_worker_pool, worker_pool};
 
 /// This extension trait defines the method [`IteratorExt::par_map`],
 /// which will use the default [`crate::pool::WebWorkerPool`] as returned by [`worker_pool()`].
@@ -27,12 +28,16 @@ where
     /// vec.iter().par_map(webworker!(my_func)).await
     /// ```
     #[allow(async_fn_in_trait)]
-    async fn par_map<R>(self, func: WebWorkerFn<T, R>) -> Vec<R>
+    async fn par_map<R, C: Codec>(self, func: WebWorkerFn<T, R, C>) -> Vec<R>
     where
         R: Serialize + for<'de> Deserialize<'de>,
     {
         let pool = worker_pool().await;
-        join_all(self.map(|arg| pool.run_internal(func, arg))).await
+        join_all(self.map(|arg| pool.run_internal(func, arg)))
+            .await
+            .into_iter()
+            .map(|res| res.expect_throw("WebWorker task failed"))
+            .collect()
     }
 
     /// The `try_par_map` function will attempt to parallelize a map operation on the default
@@ -51,7 +56,7 @@ where
     /// vec.iter().try_par_map(webworker!(my_func)).await
     /// ```
     #[allow(async_fn_in_trait)]
-    async fn try_par_map<R>(self, func: WebWorkerFn<T, R>) -> Vec<R>
+    async fn try_par_map<R, C: Codec>(self, func: WebWorkerFn<T, R, C>) -> Vec<R>
     where
         Self::Item: Into<T>,
         R: Serialize + for<'de> Deserialize<'de>,
@@ -59,7 +64,7 @@ where
         if has_worker_pool() {
             self.par_map(func).await
         } else {
-            self.map(|item| (func.func)(item.into())).collect()
+            join_all(self.map(|item| func.func.call(item.into()))).await
         }
     }
 }
@@ -95,11 +95,16 @@
 //! ```
 
 #![allow(clippy::borrowed_box)]
-pub use channel::Channel;
+pub use abort::AbortHandle;
+#[doc(hidden)]
+pub use channel::WorkerChannel;
+pub use channel::{Channel, TypedChannel, Untyped};
 pub use channel_task::ChannelTask;
 pub use global::{
-    has_worker_pool, init_optimized_worker_pool, init_worker_pool, worker_pool, AlreadyInitialized,
+    has_worker_pool, init_optimized_worker_pool, init_worker_pool, reset_worker_pool,
+    set_worker_pool_observer, worker_pool, worker_pool_stats, AlreadyInitialized,
 };
+pub use observer::PoolObserver;
 pub use pool::WorkerPoolOptions;
 pub use webworker::WebWorker;
 
@@ -112,6 +117,7 @@ pub use pool::WebWorkerPool;
 #[cfg(feature = "macros")]
 pub use wasmworker_proc_macro::*;
 
+mod abort;
 mod channel;
 mod channel_task;
 pub mod convert;
@@ -120,5 +126,13 @@ pub mod func;
 mod global;
 #[cfg(feature = "iter-ext")]
 pub mod iter_ext;
+mod metrics;
+pub mod observer;
+#[doc(hidden)]
+pub mod panic;
 pub mod pool;
+#[doc(hidden)]
+pub mod stream;
+mod timer;
+mod trace;
 mod webworker;
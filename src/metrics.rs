@@ -0,0 +1,232 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    rc::Rc,
+    time::Duration,
+};
+
+use js_sys::Uint8Array;
+
+use crate::{
+    error::TaskError,
+    observer::{PoolObserver, TaskInfo},
+    pool::FunctionStats,
+};
+
+/// The number of recent run times per function that the p95 is computed from.
+const MAX_SAMPLES: usize = 1000;
+
+/// Task and worker counters and the [`PoolObserver`], which are shared by all workers of a pool.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    /// The observer notified about tasks and workers, if any.
+    observer: RefCell<Option<Rc<dyn PoolObserver>>>,
+    /// The id of the next task.
+    next_task_id: Cell<u32>,
+    /// The counters per function name.
+    functions: RefCell<HashMap<&'static str, FunctionMetrics>>,
+    /// The total size of the arguments sent to workers.
+    bytes_sent: Cell<u64>,
+    /// The total size of the results received from workers.
+    bytes_received: Cell<u64>,
+    /// The number of workers that have been created.
+    workers_created: Cell<usize>,
+    /// The number of workers that have been terminated.
+    workers_terminated: Cell<usize>,
+}
+
+/// The counters of a single function.
+#[derive(Default)]
+struct FunctionMetrics {
+    completed: usize,
+    failed: usize,
+    /// The sum of the run times of all completed tasks in milliseconds.
+    total_ms: f64,
+    /// The run times of the most recent completed tasks in milliseconds.
+    samples: VecDeque<f64>,
+}
+
+impl Metrics {
+    /// Sets or removes the observer.
+    pub(crate) fn set_observer(&self, observer: Option<Rc<dyn PoolObserver>>) {
+        *self.observer.borrow_mut() = observer;
+    }
+
+    /// Returns whether an observer has been set.
+    pub(crate) fn has_observer(&self) -> bool {
+        self.observer.borrow().is_some()
+    }
+
+    /// Calls the observer, if any.
+    /// The observer is not borrowed during the call, so that it may replace itself.
+    fn notify(&self, f: impl FnOnce(&dyn PoolObserver)) {
+        let observer = self.observer.borrow().clone();
+        if let Some(observer) = observer {
+            f(&*observer);
+        }
+    }
+
+    /// Returns a new task id.
+    pub(crate) fn next_task_id(&self) -> u32 {
+        let id = self.next_task_id.get();
+        self.next_task_id.set(id.wrapping_add(1));
+        id
+    }
+
+    /// Records a task that has been sent to a worker.
+    /// The outcome is recorded via the returned [`TaskRecord`].
+    pub(crate) fn start_task(
+        self: &Rc<Self>,
+        task: TaskInfo,
+        bytes_sent: u32,
+        worker_terminated: Rc<Cell<bool>>,
+    ) -> TaskRecord {
+        self.bytes_sent
+            .set(self.bytes_sent.get() + bytes_sent as u64);
+        self.notify(|observer| observer.on_task_submitted(&task));
+        TaskRecord {
+            metrics: Rc::clone(self),
+            task,
+            started_at: js_sys::Date::now(),
+            worker_terminated,
+            finished: false,
+        }
+    }
+
+    /// Records that a worker has started running a task.
+    pub(crate) fn task_started(&self, task: TaskInfo) {
+        self.notify(|observer| observer.on_task_started(&task));
+    }
+
+    /// Records the creation of a worker and returns its id.
+    pub(crate) fn worker_created(&self) -> usize {
+        let id = self.workers_created.get();
+        self.workers_created.set(id + 1);
+        self.notify(|observer| observer.on_worker_spawned(id));
+        id
+    }
+
+    /// Records the termination of the worker with the given id.
+    pub(crate) fn worker_terminated(&self, worker_id: usize) {
+        self.workers_terminated
+            .set(self.workers_terminated.get() + 1);
+        self.notify(|observer| observer.on_worker_terminated(worker_id));
+    }
+
+    pub(crate) fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.get()
+    }
+
+    pub(crate) fn bytes_received(&self) -> u64 {
+        self.bytes_received.get()
+    }
+
+    pub(crate) fn workers_created(&self) -> usize {
+        self.workers_created.get()
+    }
+
+    pub(crate) fn workers_terminated(&self) -> usize {
+        self.workers_terminated.get()
+    }
+
+    /// Returns the statistics per function, sorted by function name.
+    pub(crate) fn function_stats(&self) -> Vec<FunctionStats> {
+        let mut stats = self
+            .functions
+            .borrow()
+            .iter()
+            .map(|(name, metrics)| FunctionStats {
+                name: name.to_string(),
+                completed_tasks: metrics.completed,
+                failed_tasks: metrics.failed,
+                average_ms: metrics.average_ms(),
+                p95_ms: metrics.p95_ms(),
+            })
+            .collect::<Vec<_>>();
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        stats
+    }
+
+    /// Records the run time of a completed task, or a failed task if `duration` is `None`.
+    fn record(&self, func_name: &'static str, duration: Option<f64>) {
+        let mut functions = self.functions.borrow_mut();
+        let metrics = functions.entry(func_name).or_default();
+        match duration {
+            Some(duration) => {
+                metrics.completed += 1;
+                metrics.total_ms += duration;
+                if metrics.samples.len() == MAX_SAMPLES {
+                    metrics.samples.pop_front();
+                }
+                metrics.samples.push_back(duration);
+            }
+            None => metrics.failed += 1,
+        }
+    }
+}
+
+impl FunctionMetrics {
+    fn average_ms(&self) -> f64 {
+        if self.completed == 0 {
+            return 0.0;
+        }
+        self.total_ms / self.completed as f64
+    }
+
+    /// Returns the nearest-rank 95th percentile of the recent run times.
+    fn p95_ms(&self) -> f64 {
+        let mut samples = self.samples.iter().copied().collect::<Vec<_>>();
+        samples.sort_by(f64::total_cmp);
+        let rank = (samples.len() as f64 * 0.95).ceil() as usize;
+        samples.get(rank.saturating_sub(1)).copied().unwrap_or(0.0)
+    }
+}
+
+/// Records the outcome of a task that has been sent to a worker.
+/// If it is dropped before an outcome has been recorded, the task counts as failed
+/// if its worker has been terminated, and as cancelled otherwise.
+/// Cancelled tasks count as neither completed nor failed.
+pub(crate) struct TaskRecord {
+    metrics: Rc<Metrics>,
+    task: TaskInfo,
+    /// The time the task was sent at, in milliseconds since the epoch.
+    started_at: f64,
+    worker_terminated: Rc<Cell<bool>>,
+    finished: bool,
+}
+
+impl TaskRecord {
+    /// Records the result of the task.
+    pub(crate) fn finish(mut self, result: &Result<Uint8Array, TaskError>) {
+        self.finished = true;
+        let task = &self.task;
+        match result {
+            Ok(response) => {
+                let duration = js_sys::Date::now() - self.started_at;
+                let bytes = response.length() as usize;
+                let bytes_received = &self.metrics.bytes_received;
+                bytes_received.set(bytes_received.get() + bytes as u64);
+                self.metrics.record(task.func_name, Some(duration));
+                let duration = Duration::from_secs_f64(duration.max(0.0) / 1000.0);
+                self.metrics
+                    .notify(|observer| observer.on_task_completed(task, duration, bytes));
+            }
+            Err(e) => {
+                self.metrics.record(task.func_name, None);
+                self.metrics
+                    .notify(|observer| observer.on_task_failed(task, e));
+            }
+        }
+    }
+}
+
+impl Drop for TaskRecord {
+    fn drop(&mut self) {
+        if !self.finished && self.worker_terminated.get() {
+            let task = &self.task;
+            self.metrics.record(task.func_name, None);
+            self.metrics
+                .notify(|observer| observer.on_task_failed(task, &TaskError::WorkerTerminated));
+        }
+    }
+}
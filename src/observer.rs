@@ -0,0 +1,188 @@
+//! Observers for the lifecycle of tasks and workers.
+//!
+//! A [`PoolObserver`] can be registered on a [`crate::WebWorkerPool`]
+//! via [`crate::WebWorkerPool::set_observer`] or on a [`crate::WebWorker`]
+//! via [`crate::WebWorker::set_observer`].
+//! From JavaScript, an object with the corresponding callback methods
+//! can be registered on the global pool via [`crate::set_worker_pool_observer`].
+
+use std::time::Duration;
+
+use serde::Serialize;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::error::TaskError;
+
+/// Information about a task passed to a [`PoolObserver`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TaskInfo {
+    /// The id of the task, which is unique within a pool or a standalone worker.
+    pub id: u32,
+    /// The name of the function run by the task.
+    pub func_name: &'static str,
+    /// The id of the worker the task has been sent to,
+    /// as passed to [`PoolObserver::on_worker_spawned`].
+    pub worker_id: usize,
+}
+
+/// An observer for the lifecycle of tasks and workers.
+///
+/// All methods have empty default implementations,
+/// so that only the relevant callbacks need to be implemented.
+/// The callbacks are called on the main thread and should return quickly.
+///
+/// Example:
+/// ```no_run
+/// use std::{rc::Rc, time::Duration};
+/// use wasmworker::{observer::TaskInfo, worker_pool, PoolObserver};
+///
+/// struct Logger;
+///
+/// impl PoolObserver for Logger {
+///     fn on_task_completed(&self, task: &TaskInfo, duration: Duration, _bytes: usize) {
+///         log::info!("{} took {duration:?}", task.func_name);
+///     }
+/// }
+///
+/// # async fn example() {
+/// worker_pool().await.set_observer(Some(Rc::new(Logger)));
+/// # }
+/// # fn main() {}
+/// ```
+pub trait PoolObserver {
+    /// Called when a task has been sent to a worker.
+    fn on_task_submitted(&self, _task: &TaskInfo) {}
+
+    /// Called when the worker has started running the task.
+    /// Tasks may wait in the worker before, e.g., while an async function is running.
+    fn on_task_started(&self, _task: &TaskInfo) {}
+
+    /// Called when a task has completed successfully.
+    /// The `duration` is measured from sending the task to receiving its result,
+    /// which has a size of `bytes`.
+    fn on_task_completed(&self, _task: &TaskInfo, _duration: Duration, _bytes: usize) {}
+
+    /// Called when a task has failed, including when its worker has been terminated.
+    /// Tasks that have been cancelled are not reported.
+    fn on_task_failed(&self, _task: &TaskInfo, _error: &TaskError) {}
+
+    /// Called when a worker has been created, including when a pool recreates one.
+    fn on_worker_spawned(&self, _worker_id: usize) {}
+
+    /// Called when a worker has been terminated, e.g., because it was idle or crashed.
+    fn on_worker_terminated(&self, _worker_id: usize) {}
+}
+
+/// A [`PoolObserver`] that forwards the callbacks to a JavaScript object.
+///
+/// The object may implement any of the methods `onTaskSubmitted`, `onTaskStarted`,
+/// `onTaskCompleted`, `onTaskFailed`, `onWorkerSpawned` and `onWorkerTerminated`.
+/// Each method receives an event object with the fields `workerId` and,
+/// for task events, `taskId` and `funcName`.
+/// Completed tasks additionally have the fields `durationMs` and `bytes`,
+/// failed tasks the field `error`.
+///
+/// ```js
+/// await setWorkerPoolObserver({
+///     onTaskCompleted: (event) => console.log(event.funcName, event.durationMs),
+/// });
+/// ```
+pub struct JsPoolObserver {
+    object: JsValue,
+}
+
+/// The event object passed to the methods of a [`JsPoolObserver`].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsEvent<'a> {
+    worker_id: usize,
+    task_id: Option<u32>,
+    func_name: Option<&'a str>,
+    duration_ms: Option<f64>,
+    bytes: Option<usize>,
+    error: Option<String>,
+}
+
+impl JsEvent<'_> {
+    fn task(task: &TaskInfo) -> Self {
+        Self {
+            task_id: Some(task.id),
+            func_name: Some(task.func_name),
+            ..Self::worker(task.worker_id)
+        }
+    }
+
+    fn worker(worker_id: usize) -> Self {
+        Self {
+            worker_id,
+            task_id: None,
+            func_name: None,
+            duration_ms: None,
+            bytes: None,
+            error: None,
+        }
+    }
+}
+
+impl JsPoolObserver {
+    /// Creates an observer that forwards the callbacks to the given JavaScript object.
+    pub fn new(object: JsValue) -> Self {
+        Self { object }
+    }
+
+    /// Calls the given method of the object, if it exists.
+    fn call(&self, method: &str, event: JsEvent) {
+        let Ok(func) = js_sys::Reflect::get(&self.object, &JsValue::from_str(method)) else {
+            return;
+        };
+        let Some(func) = func.dyn_ref::<js_sys::Function>() else {
+            return;
+        };
+        let Ok(event) = serde_wasm_bindgen::to_value(&event) else {
+            return;
+        };
+        if let Err(e) = func.call1(&self.object, &event) {
+            crate::trace::trace_warn!("Pool observer method {method} failed: {e:?}");
+        }
+    }
+}
+
+impl PoolObserver for JsPoolObserver {
+    fn on_task_submitted(&self, task: &TaskInfo) {
+        self.call("onTaskSubmitted", JsEvent::task(task));
+    }
+
+    fn on_task_started(&self, task: &TaskInfo) {
+        self.call("onTaskStarted", JsEvent::task(task));
+    }
+
+    fn on_task_completed(&self, task: &TaskInfo, duration: Duration, bytes: usize) {
+        self.call(
+            "onTaskCompleted",
+            JsEvent {
+                duration_ms: Some(duration.as_secs_f64() * 1000.0),
+                bytes: Some(bytes),
+                ..JsEvent::task(task)
+            },
+        );
+    }
+
+    fn on_task_failed(&self, task: &TaskInfo, error: &TaskError) {
+        self.call(
+            "onTaskFailed",
+            JsEvent {
+                error: Some(error.to_string()),
+                ..JsEvent::task(task)
+            },
+        );
+    }
+
+    fn on_worker_spawned(&self, worker_id: usize) {
+        self.call("onWorkerSpawned", JsEvent::worker(worker_id));
+    }
+
+    fn on_worker_terminated(&self, worker_id: usize) {
+        self.call("onWorkerTerminated", JsEvent::worker(worker_id));
+    }
+}
@@ -0,0 +1,97 @@
+//! Worker-side panic handling used by the code generated by the
+//! `#[webworker_fn]` and `#[webworker_channel_fn]` macros.
+//!
+//! On `wasm32-unknown-unknown`, panics abort by trapping the wasm instance,
+//! so they cannot be caught from Rust. Instead, a panic hook records the
+//! message and location in the worker's global scope right before the trap.
+//! The worker dispatcher catches the resulting exception, picks up the
+//! recorded information and reports it back to the main thread.
+//! If the crate is built with `panic = "unwind"`, panics are additionally
+//! caught in Rust, which keeps the wasm instance in a consistent state.
+
+use std::{any::Any, future::Future, sync::Once};
+
+use serde::Serialize;
+use wasm_bindgen::JsValue;
+
+/// The global property the panic information is stored in.
+/// Must match the property read by the dispatcher in [`crate::webworker::js`].
+const PANIC_PROPERTY: &str = "__wasmworker_panic";
+
+/// The panic information reported to the dispatcher.
+#[derive(Serialize)]
+struct PanicInfo {
+    message: String,
+    location: Option<String>,
+}
+
+/// Installs a panic hook that records panic information for the dispatcher.
+/// Any previously installed hook is still called afterwards.
+/// Calling this function multiple times has no further effect.
+pub fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let prev = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let panic = PanicInfo {
+                message: payload_message(info.payload()),
+                location: info
+                    .location()
+                    .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            };
+            if let Ok(value) = serde_wasm_bindgen::to_value(&panic) {
+                let _ = js_sys::Reflect::set(
+                    &js_sys::global(),
+                    &JsValue::from_str(PANIC_PROPERTY),
+                    &value,
+                );
+            }
+            prev(info);
+        }));
+    });
+}
+
+/// Extracts the message from a panic payload.
+fn payload_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+/// Runs `f` and converts a panic into a JS exception.
+/// Without unwinding support, the panic traps the wasm instance instead.
+#[cfg(panic = "unwind")]
+pub fn catch_panic<R>(f: impl FnOnce() -> R) -> Result<R, JsValue> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+        .map_err(|payload| JsValue::from_str(&payload_message(&*payload)))
+}
+
+/// Runs `f` and converts a panic into a JS exception.
+/// Without unwinding support, the panic traps the wasm instance instead.
+#[cfg(not(panic = "unwind"))]
+pub fn catch_panic<R>(f: impl FnOnce() -> R) -> Result<R, JsValue> {
+    Ok(f())
+}
+
+/// Awaits `f` and converts a panic into a JS exception.
+/// Without unwinding support, the panic traps the wasm instance instead.
+#[cfg(panic = "unwind")]
+pub async fn catch_panic_async<R>(f: impl Future<Output = R>) -> Result<R, JsValue> {
+    use futures::FutureExt;
+
+    std::panic::AssertUnwindSafe(f)
+        .catch_unwind()
+        .await
+        .map_err(|payload| JsValue::from_str(&payload_message(&*payload)))
+}
+
+/// Awaits `f` and converts a panic into a JS exception.
+/// Without unwinding support, the panic traps the wasm instance instead.
+#[cfg(not(panic = "unwind"))]
+pub async fn catch_panic_async<R>(f: impl Future<Output = R>) -> Result<R, JsValue> {
+    Ok(f.await)
+}
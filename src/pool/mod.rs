@@ -2,13 +2,27 @@
 // `WorkerPoolOptions` clone every field, including the `Copy` ones.
 #![allow(clippy::clone_on_copy)]
 
-use std::{borrow::Borrow, cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::hash_map::DefaultHasher,
+    future::Future,
+    hash::{Hash, Hasher},
+    rc::Rc,
+    time::Duration,
+};
 
-use futures::future::join_all;
-use js_sys::wasm_bindgen::{prelude::wasm_bindgen, UnwrapThrowExt};
-use scheduler::Scheduler;
-pub use scheduler::Strategy;
+use futures::{
+    future::{self, join_all, Either},
+    stream, Stream, StreamExt,
+};
+use js_sys::{
+    wasm_bindgen::{prelude::wasm_bindgen, UnwrapThrowExt},
+    Uint8Array,
+};
+use scheduler::{least_loaded, QueuePermit, Scheduler};
+pub use scheduler::{Priority, Strategy};
 use serde::{Deserialize, Serialize};
+pub use stats::{FunctionStats, PoolStats, SlotState};
 use wasm_bindgen::prelude::Closure;
 use wasm_bindgen::JsCast;
 
@@ -16,13 +30,19 @@ use wasm_bindgen_futures::JsFuture;
 use web_sys::window;
 
 use crate::{
+    abort::{abortable, AbortHandle},
     channel_task::ChannelTask,
-    error::InitError,
-    func::{WebWorkerChannelFn, WebWorkerFn},
+    convert::Codec,
+    error::{InitError, TaskError, TryRunError},
+    func::{WebWorkerChannelFn, WebWorkerFn, WebWorkerStreamFn, WorkerInit},
+    metrics::Metrics,
+    observer::PoolObserver,
+    stream::{into_stream, STREAM_WINDOW},
     WebWorker,
 };
 
 mod scheduler;
+mod stats;
 
 /// This struct can be used to configure all options of the [`WebWorkerPool`].
 ///
@@ -49,6 +69,22 @@ pub struct WorkerPoolOptions {
     pub strategy: Option<Strategy>,
     /// The number of workers that will be spawned. This defaults to `navigator.hardwareConcurrency`.
     pub num_workers: Option<usize>,
+    /// The minimum number of workers, which are spawned on creation and kept alive
+    /// regardless of [`WorkerPoolOptions::idle_timeout_ms`].
+    /// Workers that crashed are not replaced right away. Once the pool receives its next task,
+    /// it starts replacements in the background until it has this number of workers again.
+    /// Default: `None`, i.e., all workers are spawned on creation and may be terminated when idle.
+    pub min_workers: Option<usize>,
+    /// The maximum number of workers, which overrides `num_workers`.
+    /// Together with [`WorkerPoolOptions::min_workers`], the pool grows on load
+    /// and shrinks back via [`WorkerPoolOptions::idle_timeout_ms`].
+    pub max_workers: Option<usize>,
+    /// The average number of in-flight tasks per active worker from which on
+    /// the pool starts another worker in the background, if it has fewer than
+    /// [`WorkerPoolOptions::max_workers`] workers.
+    /// Until the new worker is ready, tasks are scheduled to the existing workers.
+    /// Default: `1`.
+    pub scale_up_threshold: Option<usize>,
     /// Whether to precompile and share the WASM module across workers for bandwidth optimization.
     /// This reduces the number of WASM fetches from N (one per worker) to 1 (shared across all workers).
     pub precompile_wasm: Option<bool>,
@@ -56,8 +92,40 @@ pub struct WorkerPoolOptions {
     /// after being idle for this duration. They are transparently recreated when new tasks arrive.
     /// Default: `None` (no timeout, workers live for the pool's lifetime).
     pub idle_timeout_ms: Option<u32>,
+    /// Default task timeout in milliseconds. Tasks that do not complete in time fail with
+    /// [`TaskError::Timeout`] and their worker is terminated and recreated on demand.
+    /// [`WebWorkerPool::run_with_timeout`] overrides this value for a single task.
+    /// Default: `None` (no timeout).
+    pub task_timeout_ms: Option<u32>,
+    /// The maximum number of tasks a single worker runs concurrently.
+    /// Further tasks wait in the pool's queue until a worker becomes free.
+    /// Default: `1` for [`Strategy::Queue`] or if [`WorkerPoolOptions::max_queued_tasks`] is set,
+    /// unlimited otherwise.
+    pub worker_concurrency: Option<usize>,
+    /// The maximum number of tasks waiting in the pool's queue
+    /// before [`WebWorkerPool::try_run`] and the related methods fail with [`TryRunError::Full`].
+    /// All other methods keep queueing tasks.
+    /// Setting this option limits [`WorkerPoolOptions::worker_concurrency`] to one task per worker,
+    /// unless it is set explicitly.
+    /// Default: `None` (no task may wait).
+    pub max_queued_tasks: Option<usize>,
+    /// Priority aging interval in milliseconds for the pool's queue.
+    /// A task waiting in the queue is raised by one [`Priority`] level
+    /// each time this interval elapses, up to [`Priority::High`],
+    /// so that low-priority tasks are not starved by a steady stream of higher-priority ones.
+    /// Default: `None` (no aging).
+    pub priority_aging_ms: Option<u32>,
+    /// Whether to forward the records of the [`log`] crate emitted inside worker functions
+    /// to the main thread, where they are re-emitted with the ids of the worker and the task.
+    /// Records are only forwarded up to [`log::max_level`] of the main thread.
+    /// Output written to the console directly, e.g., via `console.log`, is not forwarded.
+    /// Default: `false`.
+    pub forward_logs: Option<bool>,
     /// Pre-compiled WASM module to share across workers. Internal use only.
     pub(crate) wasm_module: Option<js_sys::WebAssembly::Module>,
+    /// The function each worker runs after loading the module,
+    /// set via [`WorkerPoolOptions::set_init_fn`].
+    pub(crate) init: Option<WorkerInit>,
 }
 
 #[wasm_bindgen]
@@ -70,6 +138,34 @@ impl WorkerPoolOptions {
 }
 
 impl WorkerPoolOptions {
+    /// Sets a function that each worker runs once after loading the module
+    /// and before it accepts tasks, e.g., to load a model or build lookup tables.
+    /// The function also runs whenever the pool recreates a worker,
+    /// e.g., after it has been terminated for being idle.
+    ///
+    /// The function is created via the [`crate::webworker!`] macro like any other worker function
+    /// and may be `async`. Unlike a `#[wasm_bindgen(start)]` function, it does not run on the main thread.
+    /// If it panics, creating the worker fails with [`InitError::InitFunction`].
+    ///
+    /// This function fails with [`TaskError::Serialization`] if `arg` cannot be serialized.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let mut options = WorkerPoolOptions::new();
+    /// options.set_init_fn(webworker!(load_model), &model_url)?;
+    /// ```
+    pub fn set_init_fn<T: Serialize, C: Codec>(
+        &mut self,
+        func: WebWorkerFn<T, (), C>,
+        arg: &T,
+    ) -> Result<(), TaskError> {
+        self.init = Some(WorkerInit {
+            func_name: func.name,
+            arg: C::encode(arg)?,
+        });
+        Ok(())
+    }
+
     /// Returns the path to be used.
     fn path(&self) -> Option<&str> {
         self.path.as_deref()
@@ -86,7 +182,7 @@ impl WorkerPoolOptions {
 
     /// Returns the number of workers, which defaults `navigator.hardwareConcurrency`.
     fn num_workers(&self) -> usize {
-        self.num_workers.unwrap_or_else(|| {
+        self.max_workers.or(self.num_workers).unwrap_or_else(|| {
             window()
                 .expect_throw("Window missing")
                 .navigator()
@@ -125,15 +221,18 @@ enum WorkerSlot {
     Active(WebWorker),
     /// Worker is being created (prevents duplicate creation during async init).
     Creating,
-    /// Worker was terminated by idle timeout and can be recreated.
+    /// Worker was terminated by idle timeout or after a crash and can be recreated.
     Empty,
 }
 
+/// A worker slot (per-slot RefCell for independent borrowing).
+/// Tasks keep their slot alive, even if it is removed from the pool by a resize.
+type Slot = Rc<RefCell<WorkerSlot>>;
+
 pub struct WebWorkerPool {
-    /// The worker slots (per-slot RefCell for independent borrowing).
-    slots: Rc<Vec<RefCell<WorkerSlot>>>,
-    /// The total number of slots (pool capacity).
-    num_slots: usize,
+    /// The worker slots. The list is only borrowed briefly,
+    /// so that [`WebWorkerPool::resize`] can change it at any time.
+    slots: Rc<RefCell<Vec<Slot>>>,
     /// The internal scheduler that is used to distribute the tasks.
     scheduler: Scheduler,
     /// Pre-compiled WASM module shared across workers (kept alive to prevent dropping)
@@ -142,12 +241,68 @@ pub struct WebWorkerPool {
     /// Config retained for worker re-creation.
     pool_path: Option<String>,
     pool_path_bg: Option<String>,
+    /// Whether the workers forward the log records of their tasks.
+    forward_logs: bool,
+    /// The function each worker runs after loading the module.
+    init: Option<WorkerInit>,
     /// Idle checker setInterval closure (prevent GC).
     _idle_checker_cb: Option<Closure<dyn FnMut()>>,
     /// Idle checker interval ID (for clearInterval on Drop).
     _idle_checker_id: Option<i32>,
+    /// The number of workers that are kept alive.
+    min_workers: usize,
+    /// The average load per active worker at which another worker is started.
+    scale_up_threshold: usize,
     /// Notify waiting tasks when a worker becomes available after creation.
-    worker_ready: tokio::sync::Notify,
+    worker_ready: Rc<tokio::sync::Notify>,
+    /// The number of crashed workers that have been discarded for recreation.
+    respawns: Cell<usize>,
+    /// The tasks that have been submitted and have not finished yet.
+    tasks: Rc<TaskTracker>,
+    /// Whether the pool has been shut down and does not accept new tasks.
+    closed: Cell<bool>,
+    /// Whether the pool's workers have been terminated on shutdown.
+    terminated: Rc<Cell<bool>>,
+    /// The default timeout for tasks.
+    task_timeout: Option<Duration>,
+    /// The task and worker counters shared by all workers.
+    metrics: Rc<Metrics>,
+}
+
+/// Tracks the tasks that have been submitted to a pool and have not finished yet.
+#[derive(Default)]
+struct TaskTracker {
+    /// The number of unfinished tasks.
+    count: Cell<usize>,
+    /// Notified whenever a task has finished.
+    finished: tokio::sync::Notify,
+}
+
+/// A task's admission to the pool, which is held until the task has finished.
+pub(crate) struct Admission {
+    /// The task's place in the pool's queue, if the pool limits its workers' concurrency.
+    _permit: Option<QueuePermit>,
+    tasks: Rc<TaskTracker>,
+}
+
+impl Admission {
+    /// Registers a new task with the tracker.
+    fn new(tasks: &Rc<TaskTracker>) -> Self {
+        tasks.count.set(tasks.count.get() + 1);
+        Self {
+            _permit: None,
+            tasks: Rc::clone(tasks),
+        }
+    }
+}
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        // Release the place in the queue first, so that the count reflects the next task.
+        self._permit.take();
+        self.tasks.count.set(self.tasks.count.get() - 1);
+        self.tasks.finished.notify_waiters();
+    }
 }
 
 impl Drop for WebWorkerPool {
@@ -214,38 +369,63 @@ impl WebWorkerPool {
             };
 
         let num_slots = options.num_workers().max(1);
-        let worker_inits = (0..num_slots).map(|_| {
+        let min_workers = options.min_workers.unwrap_or(0).min(num_slots);
+        let num_initial = options.min_workers.map_or(num_slots, |_| min_workers);
+        let metrics = Rc::<Metrics>::default();
+        let forward_logs = options.forward_logs.unwrap_or(false);
+        let worker_inits = (0..num_initial).map(|_| {
             // Do not impose a task limit.
-            WebWorker::with_path_and_module(
+            WebWorker::with_metrics(
                 options.path(),
                 options.path_bg(),
                 None,
                 wasm_module.clone(),
+                Rc::clone(&metrics),
+                forward_logs,
+                options.init.as_ref(),
             )
         });
         let workers = join_all(worker_inits).await;
         let workers = workers.into_iter().collect::<Result<Vec<_>, _>>()?;
 
-        let slots: Rc<Vec<RefCell<WorkerSlot>>> = Rc::new(
+        let slots: Rc<RefCell<Vec<Slot>>> = Rc::new(RefCell::new(
             workers
                 .into_iter()
-                .map(|w| RefCell::new(WorkerSlot::Active(w)))
+                .map(WorkerSlot::Active)
+                .chain((num_initial..num_slots).map(|_| WorkerSlot::Empty))
+                .map(|slot| Rc::new(RefCell::new(slot)))
                 .collect(),
-        );
+        ));
 
         // Set up idle timeout checker if configured.
         let (idle_checker_cb, idle_checker_id) = if let Some(timeout) = options.idle_timeout_ms {
             let slots_clone = Rc::clone(&slots);
             let cb = Closure::<dyn FnMut()>::new(move || {
                 let now = js_sys::Date::now();
-                for i in 0..slots_clone.len() {
-                    let should_terminate = {
-                        let s = slots_clone[i].borrow();
-                        matches!(&*s, WorkerSlot::Active(ref w)
-                            if w.current_load() == 0 && (now - w.last_active()) >= timeout as f64)
+                let slots = slots_clone.borrow();
+                let mut num_active = slots
+                    .iter()
+                    .filter(|slot| {
+                        matches!(
+                            slot.try_borrow().as_deref(),
+                            Ok(WorkerSlot::Active(_)) | Err(_)
+                        )
+                    })
+                    .count();
+                for slot in slots.iter() {
+                    // Keep the minimum number of workers alive.
+                    if num_active <= min_workers {
+                        break;
+                    }
+                    // Slots still borrowed by a task are not idle.
+                    let Ok(mut s) = slot.try_borrow_mut() else {
+                        continue;
                     };
+                    let should_terminate = matches!(&*s, WorkerSlot::Active(ref w)
+                        if w.current_load() == 0 && (now - w.last_active()) >= timeout as f64);
                     if should_terminate {
-                        *slots_clone[i].borrow_mut() = WorkerSlot::Empty;
+                        *s = WorkerSlot::Empty;
+                        num_active -= 1;
                     }
                 }
             });
@@ -263,14 +443,33 @@ impl WebWorkerPool {
 
         Ok(Self {
             slots,
-            num_slots,
-            scheduler: Scheduler::new(options.strategy()),
+            scheduler: Scheduler::new(
+                options.strategy(),
+                num_slots,
+                options.worker_concurrency,
+                options
+                    .priority_aging_ms
+                    .map(|ms| Duration::from_millis(ms as u64)),
+                options.max_queued_tasks,
+            ),
             wasm_module,
             pool_path: options.path.clone(),
             pool_path_bg: options.path_bg.clone(),
+            forward_logs,
+            init: options.init.clone(),
             _idle_checker_cb: idle_checker_cb,
             _idle_checker_id: idle_checker_id,
-            worker_ready: tokio::sync::Notify::new(),
+            min_workers,
+            scale_up_threshold: options.scale_up_threshold.unwrap_or(1).max(1),
+            worker_ready: Rc::new(tokio::sync::Notify::new()),
+            respawns: Cell::new(0),
+            tasks: Rc::default(),
+            closed: Cell::new(false),
+            terminated: Rc::new(Cell::new(false)),
+            task_timeout: options
+                .task_timeout_ms
+                .map(|ms| Duration::from_millis(ms as u64)),
+            metrics,
         })
     }
 
@@ -285,7 +484,32 @@ impl WebWorkerPool {
     /// ```ignore
     /// worker_pool().await.run(webworker!(sort_vec), &my_vec).await
     /// ```
-    pub async fn run<T, R>(&self, func: WebWorkerFn<T, R>, arg: &T) -> R
+    pub async fn run<T, R, C: Codec>(&self, func: WebWorkerFn<T, R, C>, arg: &T) -> R
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_internal(func, arg)
+            .await
+            .expect_throw("WebWorker task failed")
+    }
+
+    /// This function differs from [`WebWorkerPool::run`] by returning a [`TaskError`]
+    /// instead of panicking if the task cannot be completed,
+    /// e.g., because the function is not exported or a worker could not be recreated.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let sorted = worker_pool().await.run_checked(webworker!(sort_vec), &my_vec).await?;
+    /// ```
+    pub async fn run_checked<T, R, C: Codec>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+    ) -> Result<R, TaskError>
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
@@ -293,6 +517,216 @@ impl WebWorkerPool {
         self.run_internal(func, arg).await
     }
 
+    /// This function outsources a task on a [`WebWorkerPool`] which has `Box<[u8]>` both as input and output
+    /// without any (de)serialization or copies on the calling side.
+    /// The whole `ArrayBuffer` underlying `arg` is transferred to the worker and is detached afterwards,
+    /// i.e., neither `arg` nor any other view on that buffer can be used by the caller anymore,
+    /// even if `arg` only covers a part of it. The result is transferred back.
+    /// This is useful for large buffers, such as images.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// Only functions taking and returning `Box<[u8]>` can be used with this method.
+    /// Otherwise, a [`TaskError::FunctionNotExported`] is returned.
+    ///
+    /// Buffers that cannot be transferred, such as views on wasm memory or already detached buffers,
+    /// fail with [`TaskError::TransferFailed`].
+    ///
+    /// Example:
+    /// ```ignore
+    /// let pixels: Uint8Array = image_data.data().into();
+    /// let blurred = worker_pool().await.run_transfer(webworker!(blur), pixels).await?;
+    /// ```
+    // Per-slot RefCell: holding a borrow across await is safe because
+    // the idle checker and the reaper skip slots that are currently borrowed.
+    #[allow(clippy::await_holding_refcell_ref)]
+    pub async fn run_transfer<C: Codec>(
+        &self,
+        func: WebWorkerFn<Box<[u8]>, Box<[u8]>, C>,
+        arg: Uint8Array,
+    ) -> Result<Uint8Array, TaskError> {
+        let _admission = self.admit(Priority::Normal).await?;
+        let slot = self.acquire_worker().await?;
+        let slot = slot.borrow();
+        match &*slot {
+            WorkerSlot::Active(worker) => {
+                worker
+                    .run_transfer_internal(func, arg, self.task_timeout)
+                    .await
+            }
+            _ => unreachable!("acquire_worker guarantees Active slot"),
+        }
+    }
+
+    /// This function differs from [`WebWorkerPool::run_checked`] by failing with [`TaskError::Timeout`]
+    /// if the task does not complete within the given `timeout`,
+    /// overriding [`WorkerPoolOptions::task_timeout_ms`].
+    /// The timeout starts once the task has been sent to a worker.
+    ///
+    /// Since a running computation cannot be interrupted otherwise,
+    /// the worker is terminated on timeout and all of its other in-flight tasks
+    /// fail with [`TaskError::WorkerTerminated`]. The pool recreates the worker on demand.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// Example:
+    /// ```ignore
+    /// worker_pool().await.run_with_timeout(webworker!(sort_vec), &my_vec, Duration::from_secs(1)).await
+    /// ```
+    pub async fn run_with_timeout<T, R, C: Codec>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+        timeout: Duration,
+    ) -> Result<R, TaskError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_with_timeout_internal(func, arg, Some(timeout), Priority::Normal, None)
+            .await
+    }
+
+    /// This function differs from [`WebWorkerPool::run_checked`] by queueing the task
+    /// with the given [`Priority`] instead of [`Priority::Normal`].
+    /// Waiting tasks with a higher priority are dispatched first.
+    ///
+    /// Tasks only wait in the pool's queue if [`WorkerPoolOptions::worker_concurrency`] is limited,
+    /// e.g., with [`Strategy::Queue`]. Otherwise, tasks are dispatched immediately,
+    /// the priority is ignored and a warning is logged once per pool.
+    /// [`WorkerPoolOptions::priority_aging_ms`] prevents low-priority tasks from starving.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// Example:
+    /// ```ignore
+    /// worker_pool().await.run_with_priority(webworker!(render_tile), &tile, Priority::High).await
+    /// ```
+    pub async fn run_with_priority<T, R, C: Codec>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+        priority: Priority,
+    ) -> Result<R, TaskError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_with_timeout_internal(func, arg, self.task_timeout, priority, None)
+            .await
+    }
+
+    /// This function differs from [`WebWorkerPool::run_checked`] by running the task
+    /// on the worker with the given index instead of letting the scheduler choose.
+    /// This is useful for functions that keep state in the worker, e.g., caches in `thread_local!`s.
+    /// If the worker has been terminated, e.g., after being idle,
+    /// it is recreated in the same slot.
+    ///
+    /// The task still waits for its turn in the pool's queue,
+    /// but may exceed [`WorkerPoolOptions::worker_concurrency`] on its worker.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// This fails with [`TaskError::InvalidWorker`] if `worker_index`
+    /// is not smaller than [`WebWorkerPool::num_workers`].
+    ///
+    /// Example:
+    /// ```ignore
+    /// worker_pool().await.run_on(0, webworker!(sort_vec), &my_vec).await
+    /// ```
+    pub async fn run_on<T, R, C: Codec>(
+        &self,
+        worker_index: usize,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+    ) -> Result<R, TaskError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        if worker_index >= self.num_workers() {
+            return Err(TaskError::InvalidWorker(worker_index));
+        }
+        self.run_with_timeout_internal(
+            func,
+            arg,
+            self.task_timeout,
+            Priority::Normal,
+            Some(worker_index),
+        )
+        .await
+    }
+
+    /// This function differs from [`WebWorkerPool::run_on`] by choosing the worker based on a key.
+    /// Tasks with the same key always run on the same worker, which is useful for
+    /// functions that keep per-key state in the worker, e.g., caches in `thread_local!`s.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// Example:
+    /// ```ignore
+    /// worker_pool().await.run_with_affinity(&document_id, webworker!(render_page), &page).await
+    /// ```
+    pub async fn run_with_affinity<K, T, R, C: Codec>(
+        &self,
+        key: &K,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+    ) -> Result<R, TaskError>
+    where
+        K: Hash + ?Sized,
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        let slot_id = self.affinity_slot(key);
+        self.run_with_timeout_internal(
+            func,
+            arg,
+            self.task_timeout,
+            Priority::Normal,
+            Some(slot_id),
+        )
+        .await
+    }
+
+    /// This function differs from [`WebWorkerPool::run_checked`] by returning an [`AbortHandle`]
+    /// alongside the task's future, which can be used to cancel the task.
+    /// A cancelled task resolves to [`TaskError::Cancelled`].
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// The task is only scheduled once the future is polled.
+    /// If the task is aborted via [`AbortHandle::abort_and_terminate`],
+    /// the worker is recreated on demand.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let pool = worker_pool().await;
+    /// let (handle, task) = pool.run_cancellable(webworker!(sort_vec), &my_vec);
+    /// handle.abort_and_terminate();
+    /// assert!(matches!(task.await, Err(TaskError::Cancelled)));
+    /// ```
+    pub fn run_cancellable<'a, T, R, C: Codec>(
+        &'a self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &'a T,
+    ) -> (AbortHandle, impl Future<Output = Result<R, TaskError>> + 'a)
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de> + 'a,
+    {
+        let (handle, registration) = AbortHandle::new_pair();
+        let task = abortable(
+            self.run_cancellable_internal(func, arg, handle.clone()),
+            registration,
+        );
+        (handle, task)
+    }
+
     /// Run an async function with bidirectional channel support on this [`WebWorkerPool`].
     ///
     /// Returns a [`ChannelTask`] that provides both the communication channel and the
@@ -302,6 +736,9 @@ impl WebWorkerPool {
     /// [`crate::webworker_channel!`] macro. This ensures type safety and that the function
     /// is correctly exposed to the worker.
     ///
+    /// This function panics if the task cannot be started, e.g., because the pool has been shut down.
+    /// Use [`WebWorkerPool::run_channel_checked`] for a fallible variant.
+    ///
     /// Example:
     /// ```ignore
     /// let task = worker_pool().await
@@ -312,12 +749,117 @@ impl WebWorkerPool {
     /// task.send(&Continue { should_continue: true });
     /// let result: ProcessResult = task.result().await;
     /// ```
-    pub async fn run_channel<T, R>(&self, func: WebWorkerChannelFn<T, R>, arg: &T) -> ChannelTask<R>
+    pub async fn run_channel<T, R, In, Out>(
+        &self,
+        func: WebWorkerChannelFn<T, R, In, Out>,
+        arg: &T,
+    ) -> ChannelTask<R, In, Out>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_channel_internal(func.name, arg, None)
+            .await
+            .expect_throw("WebWorker task failed")
+    }
+
+    /// This function differs from [`WebWorkerPool::run_channel`] by returning a [`TaskError`]
+    /// if the task cannot be started, e.g., because the pool has been shut down
+    /// or a worker could not be created.
+    pub async fn run_channel_checked<T, R, In, Out>(
+        &self,
+        func: WebWorkerChannelFn<T, R, In, Out>,
+        arg: &T,
+    ) -> Result<ChannelTask<R, In, Out>, TaskError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_channel_internal(func.name, arg, None).await
+    }
+
+    /// This function differs from [`WebWorkerPool::run_channel`] by bounding the channel in both directions.
+    ///
+    /// At most `capacity` messages are buffered on either side. Use `send_async` on the
+    /// [`Channel`](crate::Channel) or [`ChannelTask`] to wait until the other side has received
+    /// enough of the previous messages. This way, a worker that reports progress faster
+    /// than the main thread consumes it is slowed down instead of growing memory without bound.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let task = worker
+    ///     .run_channel_bounded(webworker_channel!(process_with_progress), &data, 16)
+    ///     .await;
+    /// ```
+    pub async fn run_channel_bounded<T, R, In, Out>(
+        &self,
+        func: WebWorkerChannelFn<T, R, In, Out>,
+        arg: &T,
+        capacity: u32,
+    ) -> ChannelTask<R, In, Out>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_channel_internal(func.name, arg, Some(capacity))
+            .await
+            .expect_throw("WebWorker task failed")
+    }
+
+    /// Run a function producing a stream of items on this [`WebWorkerPool`].
+    ///
+    /// The returned stream yields the items in order and ends once the worker's stream has ended.
+    /// The worker only produces a limited number of items ahead of the consumer.
+    /// Dropping the stream stops the worker from producing further items.
+    ///
+    /// The `func`: [`WebWorkerStreamFn`] argument should normally be instantiated using the
+    /// [`crate::webworker_stream!`] macro. This ensures type safety and that the function
+    /// is correctly exposed to the worker.
+    ///
+    /// The stream panics if the task could not be completed.
+    /// Use [`WebWorkerPool::run_stream_checked`] for a fallible variant.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let mut items = pin!(worker_pool().await.run_stream(webworker_stream!(count_to), &10).await);
+    /// while let Some(item) = items.next().await {
+    ///     // ...
+    /// }
+    /// ```
+    pub async fn run_stream<T, R>(
+        &self,
+        func: WebWorkerStreamFn<T, R>,
+        arg: &T,
+    ) -> impl Stream<Item = R>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_stream_checked(func, arg)
+            .await
+            .map(|item| item.expect_throw("WebWorker task failed"))
+    }
+
+    /// This function differs from [`WebWorkerPool::run_stream`] by yielding a [`TaskError`]
+    /// if the task could not be completed. The stream ends after the first error.
+    /// If the task cannot be started, e.g., because the pool has been shut down,
+    /// the error is the first and only item.
+    pub async fn run_stream_checked<T, R>(
+        &self,
+        func: WebWorkerStreamFn<T, R>,
+        arg: &T,
+    ) -> impl Stream<Item = Result<R, TaskError>>
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
     {
-        self.run_channel_internal(func, arg).await
+        match self
+            .run_channel_internal(func.name, arg, Some(STREAM_WINDOW))
+            .await
+        {
+            Ok(task) => Either::Left(into_stream(task)),
+            Err(e) => Either::Right(stream::once(future::ready(Err(e)))),
+        }
     }
 
     /// This function can outsource a task on a [`WebWorkerPool`] which has `Box<[u8]>` both as input and output.
@@ -331,50 +873,125 @@ impl WebWorkerPool {
     /// ```ignore
     /// worker_pool().await.run_bytes(webworker!(sort), &my_box).await
     /// ```
-    pub async fn run_bytes(
+    pub async fn run_bytes<C: Codec>(
         &self,
-        func: WebWorkerFn<Box<[u8]>, Box<[u8]>>,
+        func: WebWorkerFn<Box<[u8]>, Box<[u8]>, C>,
         arg: &Box<[u8]>,
     ) -> Box<[u8]> {
-        self.run_internal(func, arg).await
+        self.run_internal(func, arg)
+            .await
+            .expect_throw("WebWorker task failed")
+    }
+
+    /// This function differs from [`WebWorkerPool::run`] by returning early if the pool is saturated,
+    /// i.e., the task cannot be dispatched immediately and
+    /// [`WorkerPoolOptions::max_queued_tasks`] tasks are already waiting in the pool's queue.
+    /// In this case a [`TryRunError::Full`] error is returned.
+    /// If the task fails, e.g., because the worker panicked, a [`TryRunError::Task`] error is returned.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// If neither [`WorkerPoolOptions::worker_concurrency`] nor [`WorkerPoolOptions::max_queued_tasks`]
+    /// is set for a strategy other than [`Strategy::Queue`], this function never returns [`TryRunError::Full`].
+    ///
+    /// Example:
+    /// ```ignore
+    /// worker_pool().await.try_run(webworker!(sort_vec), &my_vec).await
+    /// ```
+    pub async fn try_run<T, R, C: Codec>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+    ) -> Result<R, TryRunError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        let admission = self.try_admit().await?;
+        Ok(self
+            .dispatch(admission, func, arg, self.task_timeout, None)
+            .await?)
+    }
+
+    /// This function differs from [`WebWorkerPool::run_bytes`] by returning early if the pool is saturated.
+    /// In this case a [`TryRunError::Full`] error is returned.
+    /// (De)serialization of values needs to be handled by the caller.
+    /// For more convenient access, use [`WebWorkerPool::try_run`] instead.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// Example:
+    /// ```ignore
+    /// worker_pool().await.try_run_bytes(webworker!(sort), &my_box).await
+    /// ```
+    pub async fn try_run_bytes<C: Codec>(
+        &self,
+        func: WebWorkerFn<Box<[u8]>, Box<[u8]>, C>,
+        arg: &Box<[u8]>,
+    ) -> Result<Box<[u8]>, TryRunError> {
+        self.try_run(func, arg).await
+    }
+
+    /// This function differs from [`WebWorkerPool::run_channel`] by returning early if the pool is saturated.
+    /// In this case a [`TryRunError::Full`] error is returned.
+    /// If the task cannot be started, e.g., because a worker could not be created,
+    /// a [`TryRunError::Task`] error is returned instead of panicking.
+    ///
+    /// The `func`: [`WebWorkerChannelFn`] argument should normally be instantiated using the
+    /// [`crate::webworker_channel!`] macro. This ensures type safety and that the function
+    /// is correctly exposed to the worker.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let task = worker_pool().await
+    ///     .try_run_channel(webworker_channel!(process_with_progress), &data)
+    ///     .await?;
+    /// ```
+    pub async fn try_run_channel<T, R, In, Out>(
+        &self,
+        func: WebWorkerChannelFn<T, R, In, Out>,
+        arg: &T,
+    ) -> Result<ChannelTask<R, In, Out>, TryRunError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        let admission = self.try_admit().await?;
+        Ok(self
+            .dispatch_channel(admission, func.name, arg, None)
+            .await?)
     }
 
     /// Acquires an active worker slot, recreating a terminated worker if needed.
-    async fn acquire_worker(&self) -> usize {
+    async fn acquire_worker(&self) -> Result<Slot, TaskError> {
         loop {
+            self.check_terminated()?;
+            self.reap_terminated_workers();
+
             let loads = self.compute_loads();
+            self.scale_up(&loads);
             if let Some(id) = self.scheduler.schedule(&loads) {
-                return id;
+                return Ok(self.slot(id));
             }
 
-            // No active workers. Find first Empty slot and recreate.
-            let empty_slot = self
-                .slots
-                .iter()
-                .position(|slot| matches!(&*slot.borrow(), WorkerSlot::Empty));
-            if let Some(i) = empty_slot {
-                *self.slots[i].borrow_mut() = WorkerSlot::Creating;
+            // No active workers with a free slot. Find first Empty slot and recreate.
+            let empty_slot = self.find_slot(|slot| matches!(slot, WorkerSlot::Empty));
+            if let Some(slot) = empty_slot {
+                let slot = self.recreate_worker(slot).await;
+                self.check_terminated()?;
+                return Ok(slot?);
             }
 
-            if let Some(slot_id) = empty_slot {
-                let worker_result = WebWorker::with_path_and_module(
-                    self.pool_path.as_deref(),
-                    self.pool_path_bg.as_deref(),
-                    None,
-                    self.wasm_module.clone(),
-                )
-                .await;
-                match worker_result {
-                    Ok(worker) => {
-                        *self.slots[slot_id].borrow_mut() = WorkerSlot::Active(worker);
-                        self.worker_ready.notify_waiters();
-                        return slot_id;
-                    }
-                    Err(_) => {
-                        *self.slots[slot_id].borrow_mut() = WorkerSlot::Empty;
-                        self.worker_ready.notify_waiters();
-                        panic!("Couldn't recreate worker");
-                    }
+            // With limited concurrency, all active workers may be busy
+            // if tasks outlived their place in the queue, e.g., after a cancellation.
+            let creating = self
+                .find_slot(|slot| matches!(slot, WorkerSlot::Creating))
+                .is_some();
+            if !creating {
+                if let Some(id) = least_loaded(&loads) {
+                    return Ok(self.slot(id));
                 }
             }
 
@@ -383,54 +1000,328 @@ impl WebWorkerPool {
         }
     }
 
+    /// Acquires the given worker slot, recreating its worker if needed.
+    async fn acquire_slot(&self, slot_id: usize) -> Result<Slot, TaskError> {
+        loop {
+            self.check_terminated()?;
+            self.reap_terminated_workers();
+
+            // The slot might have been removed by a resize in the meantime.
+            let Some(slot) = self.slots.borrow().get(slot_id).cloned() else {
+                return self.acquire_worker().await;
+            };
+            let state = match &*slot.borrow() {
+                // The reaper skips slots that are borrowed by tasks on the terminated worker.
+                WorkerSlot::Active(worker) if worker.is_terminated() => None,
+                WorkerSlot::Active(_) => return Ok(Rc::clone(&slot)),
+                WorkerSlot::Empty => Some(true),
+                WorkerSlot::Creating => Some(false),
+            };
+            match state {
+                Some(true) => {
+                    let slot = self.recreate_worker(slot).await;
+                    self.check_terminated()?;
+                    return Ok(slot?);
+                }
+                // Another task is recreating this slot — wait for it to finish.
+                Some(false) => self.worker_ready.notified().await,
+                // Wait for the failing tasks to release the slot, so that it can be reaped.
+                None => self.tasks.finished.notified().await,
+            }
+        }
+    }
+
+    /// Creates a new worker in the given [`WorkerSlot::Empty`] slot.
+    async fn recreate_worker(&self, slot: Slot) -> Result<Slot, InitError> {
+        *slot.borrow_mut() = WorkerSlot::Creating;
+        let worker_result = WebWorker::with_metrics(
+            self.pool_path.as_deref(),
+            self.pool_path_bg.as_deref(),
+            None,
+            self.wasm_module.clone(),
+            Rc::clone(&self.metrics),
+            self.forward_logs,
+            self.init.as_ref(),
+        )
+        .await;
+        let result = match worker_result {
+            // The pool has been shut down while the worker was being created.
+            Ok(worker) if self.terminated.get() => {
+                drop(worker);
+                *slot.borrow_mut() = WorkerSlot::Empty;
+                Err(InitError::PoolShutdown)
+            }
+            Ok(worker) => {
+                *slot.borrow_mut() = WorkerSlot::Active(worker);
+                Ok(slot)
+            }
+            Err(e) => {
+                *slot.borrow_mut() = WorkerSlot::Empty;
+                Err(e)
+            }
+        };
+        self.worker_ready.notify_waiters();
+        result
+    }
+
+    /// Starts another worker in the background if the pool has fewer than
+    /// `min_workers` workers or the active workers are under load.
+    fn scale_up(&self, loads: &[Option<usize>]) {
+        if self.terminated.get() {
+            return;
+        }
+        let num_active = loads.iter().flatten().count();
+        let num_creating = self
+            .slots
+            .borrow()
+            .iter()
+            .filter(|slot| matches!(slot.try_borrow().as_deref(), Ok(WorkerSlot::Creating)))
+            .count();
+        let total_load = loads.iter().flatten().sum::<usize>();
+
+        let below_min = num_active + num_creating < self.min_workers;
+        // Without active workers, the task creates a worker itself.
+        let under_load = num_active > 0
+            && num_creating == 0
+            && total_load >= self.scale_up_threshold * num_active;
+        if !below_min && !under_load {
+            return;
+        }
+
+        if let Some(slot) = self.find_slot(|slot| matches!(slot, WorkerSlot::Empty)) {
+            self.spawn_worker(slot);
+        }
+    }
+
+    /// Creates a new worker in the given [`WorkerSlot::Empty`] slot in the background.
+    fn spawn_worker(&self, slot: Slot) {
+        *slot.borrow_mut() = WorkerSlot::Creating;
+        let worker_ready = Rc::clone(&self.worker_ready);
+        let terminated = Rc::clone(&self.terminated);
+        let path = self.pool_path.clone();
+        let path_bg = self.pool_path_bg.clone();
+        let wasm_module = self.wasm_module.clone();
+        let metrics = Rc::clone(&self.metrics);
+        let forward_logs = self.forward_logs;
+        let init = self.init.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let worker_result = WebWorker::with_metrics(
+                path.as_deref(),
+                path_bg.as_deref(),
+                None,
+                wasm_module,
+                metrics,
+                forward_logs,
+                init.as_ref(),
+            )
+            .await;
+            *slot.borrow_mut() = match worker_result {
+                // The pool has been shut down while the worker was being created.
+                Ok(worker) if terminated.get() => {
+                    drop(worker);
+                    WorkerSlot::Empty
+                }
+                Ok(worker) => WorkerSlot::Active(worker),
+                // The slot is recreated on demand.
+                Err(_) => WorkerSlot::Empty,
+            };
+            worker_ready.notify_waiters();
+        });
+    }
+
+    /// Fails with [`TaskError::PoolShutdown`] if the pool's workers have been terminated,
+    /// so that they are not recreated.
+    fn check_terminated(&self) -> Result<(), TaskError> {
+        if self.terminated.get() {
+            return Err(TaskError::PoolShutdown);
+        }
+        Ok(())
+    }
+
+    /// Returns the slot with the given index.
+    fn slot(&self, slot_id: usize) -> Slot {
+        Rc::clone(&self.slots.borrow()[slot_id])
+    }
+
+    /// Returns the first slot in the given state.
+    fn find_slot(&self, predicate: impl Fn(&WorkerSlot) -> bool) -> Option<Slot> {
+        self.slots
+            .borrow()
+            .iter()
+            .find(|slot| slot.try_borrow().is_ok_and(|slot| predicate(&slot)))
+            .cloned()
+    }
+
+    /// Maps an affinity key to a worker slot.
+    /// The same key is always mapped to the same slot, as long as the pool is not resized.
+    fn affinity_slot<K: Hash + ?Sized>(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.num_workers() as u64) as usize
+    }
+
+    /// Moves the slots of terminated workers (e.g., after a crash)
+    /// back to [`WorkerSlot::Empty`], so that they are recreated on demand.
+    fn reap_terminated_workers(&self) {
+        for slot in self.slots.borrow().iter() {
+            // Slots still borrowed by failing tasks are reaped on a later call.
+            let Ok(mut s) = slot.try_borrow_mut() else {
+                continue;
+            };
+            if matches!(&*s, WorkerSlot::Active(w) if w.is_terminated()) {
+                *s = WorkerSlot::Empty;
+                // Workers terminated on shutdown are not recreated.
+                if !self.terminated.get() {
+                    self.respawns.set(self.respawns.get() + 1);
+                }
+            }
+        }
+    }
+
     /// Compute per-slot loads for the scheduler.
+    /// Terminated workers are never scheduled.
     fn compute_loads(&self) -> Vec<Option<usize>> {
         self.slots
+            .borrow()
             .iter()
             .map(|slot| match &*slot.borrow() {
-                WorkerSlot::Active(w) => Some(w.current_load()),
+                WorkerSlot::Active(w) if !w.is_terminated() => Some(w.current_load()),
                 _ => None,
             })
             .collect()
     }
 
     /// Determines the worker to run a simple task on using the scheduler
-    /// and runs the task.
+    /// and runs the task with the default timeout.
+    pub(crate) async fn run_internal<T, R, C: Codec, A>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: A,
+    ) -> Result<R, TaskError>
+    where
+        A: std::borrow::Borrow<T>,
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_with_timeout_internal(func, arg, self.task_timeout, Priority::Normal, None)
+            .await
+    }
+
+    /// Determines the worker to run a simple task on using the scheduler,
+    /// unless a slot is given, and runs the task with an optional timeout and the given priority.
+    async fn run_with_timeout_internal<T, R, C: Codec, A>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: A,
+        timeout: Option<Duration>,
+        priority: Priority,
+        slot_id: Option<usize>,
+    ) -> Result<R, TaskError>
+    where
+        A: std::borrow::Borrow<T>,
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        let admission = self.admit(priority).await?;
+        self.dispatch(admission, func, arg, timeout, slot_id).await
+    }
+
+    /// Runs a simple task that has been admitted by the scheduler,
+    /// holding its place in the queue until the task has finished.
     // Per-slot RefCell: holding a borrow across await is safe because
-    // the idle checker only terminates slots with zero load (i.e., not borrowed).
+    // the idle checker and the reaper skip slots that are currently borrowed.
     #[allow(clippy::await_holding_refcell_ref)]
-    pub(crate) async fn run_internal<T, R, A>(&self, func: WebWorkerFn<T, R>, arg: A) -> R
+    async fn dispatch<T, R, C: Codec, A>(
+        &self,
+        _admission: Admission,
+        func: WebWorkerFn<T, R, C>,
+        arg: A,
+        timeout: Option<Duration>,
+        slot_id: Option<usize>,
+    ) -> Result<R, TaskError>
     where
-        A: Borrow<T>,
+        A: std::borrow::Borrow<T>,
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
     {
-        let worker_id = self.acquire_worker().await;
-        let slot = self.slots[worker_id].borrow();
+        let slot = match slot_id {
+            Some(slot_id) => self.acquire_slot(slot_id).await?,
+            None => self.acquire_worker().await?,
+        };
+        let slot = slot.borrow();
         match &*slot {
-            WorkerSlot::Active(worker) => worker.run_internal(func, arg.borrow()).await,
+            WorkerSlot::Active(worker) => worker.run_internal(func, arg.borrow(), timeout).await,
+            _ => unreachable!("acquire_worker guarantees Active slot"),
+        }
+    }
+
+    /// Determines the worker to run a task on using the scheduler
+    /// and runs the task after registering the worker with the [`AbortHandle`].
+    // Per-slot RefCell: holding a borrow across await is safe because
+    // the idle checker and the reaper skip slots that are currently borrowed.
+    #[allow(clippy::await_holding_refcell_ref)]
+    async fn run_cancellable_internal<T, R, C: Codec>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+        handle: AbortHandle,
+    ) -> Result<R, TaskError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        let _admission = self.admit(Priority::Normal).await?;
+        let slot = self.acquire_worker().await?;
+        let slot = slot.borrow();
+        match &*slot {
+            WorkerSlot::Active(worker) => {
+                handle.set_worker(worker.handle());
+                worker.run_internal(func, arg, self.task_timeout).await
+            }
             _ => unreachable!("acquire_worker guarantees Active slot"),
         }
     }
 
     /// Determines the worker to run a channel task on using the scheduler
     /// and runs the task.
+    pub(crate) async fn run_channel_internal<T, R, In, Out>(
+        &self,
+        func_name: &'static str,
+        arg: &T,
+        capacity: Option<u32>,
+    ) -> Result<ChannelTask<R, In, Out>, TaskError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        let admission = self.admit(Priority::Normal).await?;
+        self.dispatch_channel(admission, func_name, arg, capacity)
+            .await
+    }
+
+    /// Runs a channel task that has been admitted by the scheduler.
+    /// The task keeps its place in the queue until it has finished.
     // Per-slot RefCell: holding a borrow across await is safe because
-    // the idle checker only terminates slots with zero load (i.e., not borrowed).
+    // the idle checker and the reaper skip slots that are currently borrowed.
     #[allow(clippy::await_holding_refcell_ref)]
-    pub(crate) async fn run_channel_internal<T, R>(
+    async fn dispatch_channel<T, R, In, Out>(
         &self,
-        func: WebWorkerChannelFn<T, R>,
+        admission: Admission,
+        func_name: &'static str,
         arg: &T,
-    ) -> ChannelTask<R>
+        capacity: Option<u32>,
+    ) -> Result<ChannelTask<R, In, Out>, TaskError>
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
     {
-        let worker_id = self.acquire_worker().await;
-        let slot = self.slots[worker_id].borrow();
+        let slot = self.acquire_worker().await?;
+        let slot = slot.borrow();
         match &*slot {
-            WorkerSlot::Active(worker) => worker.run_channel_internal(func, arg).await,
+            WorkerSlot::Active(worker) => Ok(worker
+                .run_channel_internal(func_name, arg, capacity)
+                .await
+                .with_admission(admission)),
             _ => unreachable!("acquire_worker guarantees Active slot"),
         }
     }
@@ -438,6 +1329,7 @@ impl WebWorkerPool {
     /// Return the number of tasks currently queued to this worker pool.
     pub fn current_load(&self) -> usize {
         self.slots
+            .borrow()
             .iter()
             .map(|slot| match &*slot.borrow() {
                 WorkerSlot::Active(w) => w.current_load(),
@@ -448,17 +1340,164 @@ impl WebWorkerPool {
 
     /// Return the total number of worker slots in the pool (pool capacity).
     pub fn num_workers(&self) -> usize {
-        self.num_slots
+        self.slots.borrow().len()
     }
 
     /// Return the number of currently active (non-terminated) workers.
     pub fn num_active_workers(&self) -> usize {
         self.slots
+            .borrow()
             .iter()
-            .filter(|s| matches!(&*RefCell::borrow(s), WorkerSlot::Active(_)))
+            .filter(|s| matches!(&*s.borrow(), WorkerSlot::Active(w) if !w.is_terminated()))
             .count()
     }
 
+    /// Return the number of crashed workers that have been discarded,
+    /// so that their slots are recreated on demand.
+    /// A worker counts as crashed if it reported an error or its wasm instance trapped.
+    pub fn num_respawns(&self) -> usize {
+        self.respawns.get()
+    }
+
+    /// Returns a snapshot of the pool's state, including the state of each worker slot,
+    /// the number of queued and in-flight tasks, and task statistics per function.
+    ///
+    /// The snapshot of the global pool can also be polled from JavaScript
+    /// via [`crate::worker_pool_stats`].
+    pub fn stats(&self) -> PoolStats {
+        let slots = self
+            .slots
+            .borrow()
+            .iter()
+            .map(|slot| match &*slot.borrow() {
+                WorkerSlot::Active(w) if !w.is_terminated() => SlotState::Active,
+                WorkerSlot::Creating => SlotState::Creating,
+                _ => SlotState::Empty,
+            })
+            .collect();
+        let functions = self.metrics.function_stats();
+        PoolStats {
+            slots,
+            queued_tasks: self.scheduler.num_queued(),
+            in_flight_tasks: self.current_load(),
+            completed_tasks: functions.iter().map(|f| f.completed_tasks).sum(),
+            failed_tasks: functions.iter().map(|f| f.failed_tasks).sum(),
+            workers_created: self.metrics.workers_created(),
+            workers_terminated: self.metrics.workers_terminated(),
+            bytes_sent: self.metrics.bytes_sent(),
+            bytes_received: self.metrics.bytes_received(),
+            functions,
+        }
+    }
+
+    /// Sets the [`PoolObserver`] that is notified about the pool's tasks and workers,
+    /// or removes it if `None` is given.
+    ///
+    /// The observer of the global pool can also be set from JavaScript
+    /// via [`crate::set_worker_pool_observer`].
+    pub fn set_observer(&self, observer: Option<Rc<dyn PoolObserver>>) {
+        self.metrics.set_observer(observer);
+    }
+
+    /// Changes the number of worker slots in the pool to `num_workers` (at least one).
+    ///
+    /// New workers are spawned right away, and this function fails with an [`InitError`]
+    /// if one of them cannot be created. Their slots are then recreated on demand.
+    /// When shrinking the pool, the removed workers stop receiving new tasks
+    /// and are terminated once their in-flight tasks have finished.
+    ///
+    /// Keys passed to [`WebWorkerPool::run_with_affinity`] may map to different workers
+    /// after a resize.
+    ///
+    /// Fails with [`InitError::PoolShutdown`] once the pool has been shut down.
+    pub async fn resize(&self, num_workers: usize) -> Result<(), InitError> {
+        if self.closed.get() {
+            return Err(InitError::PoolShutdown);
+        }
+        let num_workers = num_workers.max(1);
+        let num_slots = self.num_workers();
+        self.scheduler.resize(num_workers);
+
+        if num_workers >= num_slots {
+            let new_slots = (num_slots..num_workers)
+                .map(|_| Rc::new(RefCell::new(WorkerSlot::Empty)))
+                .collect::<Vec<_>>();
+            self.slots.borrow_mut().extend(new_slots.iter().cloned());
+            let results =
+                join_all(new_slots.into_iter().map(|slot| self.recreate_worker(slot))).await;
+            return results
+                .into_iter()
+                .try_for_each(|result| result.map(|_| ()));
+        }
+
+        let removed = self.slots.borrow_mut().split_off(num_workers);
+        // Tasks keep their slot alive while they are dispatched.
+        let is_busy = |slot: &Slot| match &*slot.borrow() {
+            WorkerSlot::Active(w) => w.current_load() > 0 || Rc::strong_count(slot) > 1,
+            _ => false,
+        };
+        while removed.iter().any(is_busy) {
+            self.tasks.finished.notified().await;
+        }
+        Ok(())
+    }
+
+    /// Shuts down the pool gracefully.
+    ///
+    /// The pool stops accepting new tasks, which fail with [`TaskError::PoolShutdown`],
+    /// waits for all submitted tasks to finish, including those still waiting in the queue,
+    /// and terminates all workers afterwards.
+    pub async fn shutdown(&self) {
+        self.closed.set(true);
+        while self.tasks.count.get() > 0 {
+            self.tasks.finished.notified().await;
+        }
+        self.terminate_now();
+    }
+
+    /// Terminates all workers immediately.
+    ///
+    /// The pool stops accepting new tasks, which fail with [`TaskError::PoolShutdown`],
+    /// like the tasks still waiting in the queue.
+    /// All in-flight tasks fail with [`TaskError::WorkerTerminated`].
+    pub fn terminate_now(&self) {
+        self.closed.set(true);
+        self.terminated.set(true);
+        for slot in self.slots.borrow().iter() {
+            if let WorkerSlot::Active(worker) = &*slot.borrow() {
+                worker.handle().terminate();
+            }
+        }
+        self.reap_terminated_workers();
+    }
+
+    /// Returns whether the pool has been shut down via [`WebWorkerPool::shutdown`]
+    /// or [`WebWorkerPool::terminate_now`].
+    pub fn is_shut_down(&self) -> bool {
+        self.closed.get()
+    }
+
+    /// Admits a task to the pool, waiting for its turn in the queue.
+    async fn admit(&self, priority: Priority) -> Result<Admission, TaskError> {
+        if self.closed.get() {
+            return Err(TaskError::PoolShutdown);
+        }
+        let mut admission = Admission::new(&self.tasks);
+        admission._permit = self.scheduler.enqueue(priority).await;
+        Ok(admission)
+    }
+
+    /// This function differs from [`WebWorkerPool::admit`] by failing with [`TryRunError::Full`]
+    /// if the pool is saturated.
+    async fn try_admit(&self) -> Result<Admission, TryRunError> {
+        if self.closed.get() {
+            return Err(TaskError::PoolShutdown.into());
+        }
+        let mut admission = Admission::new(&self.tasks);
+        admission._permit = self.scheduler.try_enqueue(Priority::Normal).await?;
+        Ok(admission)
+    }
+
     /// Create a worker pool with a pre-compiled WASM module for optimal bandwidth usage.
     /// This method pre-compiles the WASM module once and shares it across all workers,
     /// reducing bandwidth usage compared to each worker loading the WASM independently.
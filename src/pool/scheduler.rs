@@ -1,7 +1,15 @@
-use std::cell::Cell;
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+    time::Duration,
+};
 
+use tokio::sync::oneshot;
 use wasm_bindgen::prelude::wasm_bindgen;
 
+use crate::{error::Full, trace::trace_warn};
+
 /// This enumeration contains the supported strategies for distributing
 /// tasks within the worker pool.
 ///
@@ -23,6 +31,36 @@ pub enum Strategy {
     /// If more than one worker has the same number of tasks scheduled, the first
     /// one is chosen.
     LoadBased,
+    /// The queue strategy keeps tasks in a central queue and only dispatches a task
+    /// once a worker has a free slot, i.e., runs fewer tasks than
+    /// [`super::WorkerPoolOptions::worker_concurrency`], which defaults to one.
+    /// Tasks are dispatched by [`Priority`] and in the order they were submitted.
+    /// In contrast to the other strategies, a long task does not delay
+    /// the tasks submitted after it while other workers are idle.
+    ///
+    /// Otherwise, the strategy behaves like the load-based strategy.
+    Queue,
+}
+
+/// The priority of a task in the queue of a [`Strategy::Queue`] pool.
+///
+/// Waiting tasks with a higher priority are dispatched first,
+/// tasks with the same priority in the order they were submitted.
+/// Priorities have no effect if the pool dispatches tasks immediately,
+/// i.e., with other strategies and no [`super::WorkerPoolOptions::worker_concurrency`] limit.
+///
+/// If re-exported, the priority can also be accessed from JavaScript.
+#[non_exhaustive] // forward compatibility
+#[wasm_bindgen]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// For background work, such as prefetching.
+    Low,
+    /// The priority of all tasks that do not specify one.
+    #[default]
+    Normal,
+    /// For latency-critical work, such as rendering visible content.
+    High,
 }
 
 /// The internal scheduler object, which contains necessary additional state
@@ -33,28 +71,108 @@ pub(super) struct Scheduler {
     /// The currently chosen worker.
     /// This state is only relevant for the round-robin strategy.
     current_worker: Cell<usize>,
+    /// The maximum number of tasks a worker runs concurrently, if limited.
+    concurrency: Option<usize>,
+    /// The central task queue, which exists if the concurrency is limited.
+    queue: Option<Rc<TaskQueue>>,
+    /// Whether a warning about priorities being ignored has been emitted.
+    warned_priority: Cell<bool>,
 }
 
 impl Scheduler {
     /// Initialize a new scheduler.
-    pub(super) fn new(strategy: Strategy) -> Self {
+    ///
+    /// The queue strategy and a `max_queued` limit restrict the `concurrency`
+    /// to one task per worker by default.
+    /// With `aging`, waiting tasks are raised by one priority level per elapsed interval.
+    /// `max_queued` limits the number of waiting tasks for [`Scheduler::try_enqueue`].
+    pub(super) fn new(
+        strategy: Strategy,
+        num_slots: usize,
+        concurrency: Option<usize>,
+        aging: Option<Duration>,
+        max_queued: Option<usize>,
+    ) -> Self {
+        let concurrency = concurrency
+            .or((strategy == Strategy::Queue || max_queued.is_some()).then_some(1))
+            .map(|concurrency| concurrency.max(1));
         Self {
             strategy,
             current_worker: Cell::new(0),
+            concurrency,
+            queue: concurrency.map(|concurrency| {
+                Rc::new(TaskQueue::new(
+                    num_slots * concurrency,
+                    aging,
+                    max_queued.unwrap_or(0),
+                ))
+            }),
+            warned_priority: Cell::new(false),
+        }
+    }
+
+    /// Waits until the task may be dispatched to a worker.
+    ///
+    /// If the concurrency is limited, this returns a permit that needs to be held until
+    /// the task has finished. Otherwise, this returns immediately
+    /// and warns once if a priority other than [`Priority::Normal`] is ignored.
+    pub(super) async fn enqueue(&self, priority: Priority) -> Option<QueuePermit> {
+        match &self.queue {
+            Some(queue) => Some(TaskQueue::acquire(queue, priority).await),
+            None => {
+                if priority != Priority::Normal && !self.warned_priority.replace(true) {
+                    trace_warn!(
+                        "Task priorities are ignored because the pool has no queue, \
+                         set `worker_concurrency` or use `Strategy::Queue`"
+                    );
+                }
+                None
+            }
+        }
+    }
+
+    /// Adapts the queue to a new number of worker slots.
+    pub(super) fn resize(&self, num_slots: usize) {
+        if let (Some(queue), Some(concurrency)) = (&self.queue, self.concurrency) {
+            TaskQueue::set_permits(queue, num_slots * concurrency);
         }
     }
 
+    /// This function differs from [`Scheduler::enqueue`] by failing with [`Full`]
+    /// if the task cannot be dispatched immediately and the queue is full.
+    pub(super) async fn try_enqueue(
+        &self,
+        priority: Priority,
+    ) -> Result<Option<QueuePermit>, Full> {
+        match &self.queue {
+            Some(queue) => TaskQueue::try_acquire(queue, priority).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the number of tasks waiting in the queue.
+    pub(super) fn num_queued(&self) -> usize {
+        self.queue.as_ref().map_or(0, |queue| queue.num_waiting())
+    }
+
     /// Given per-slot loads, apply the strategy and determine which worker
-    /// should receive the next task. Returns `None` if no active workers exist.
+    /// should receive the next task. Returns `None` if no active workers exist
+    /// or, if the concurrency is limited, all active workers are busy.
     ///
     /// Each entry in `loads` is `Some(current_load)` for active workers,
     /// or `None` for terminated/creating slots.
     pub(super) fn schedule(&self, loads: &[Option<usize>]) -> Option<usize> {
+        // Busy workers are not scheduled.
+        let loads = loads
+            .iter()
+            .map(|load| load.filter(|&load| self.concurrency.is_none_or(|c| load < c)))
+            .collect::<Vec<_>>();
         match self.strategy {
             Strategy::RoundRobin => {
                 let num = loads.len();
                 for _ in 0..num {
-                    let id = self.current_worker.get();
+                    // The pool might have been resized since the last task.
+                    let id = self.current_worker.get() % num;
                     self.current_worker.set((id + 1) % num);
                     if loads[id].is_some() {
                         return Some(id);
@@ -62,12 +180,175 @@ impl Scheduler {
                 }
                 None
             }
-            Strategy::LoadBased => loads
-                .iter()
-                .enumerate()
-                .filter_map(|(i, load)| load.map(|l| (i, l)))
-                .min_by_key(|(_i, load)| *load)
-                .map(|(i, _)| i),
+            Strategy::LoadBased | Strategy::Queue => least_loaded(&loads),
         }
     }
 }
+
+/// Returns the active worker with the lowest number of tasks.
+/// If more than one worker has the same number of tasks, the first one is chosen.
+pub(super) fn least_loaded(loads: &[Option<usize>]) -> Option<usize> {
+    loads
+        .iter()
+        .enumerate()
+        .filter_map(|(i, load)| load.map(|l| (i, l)))
+        .min_by_key(|(_i, load)| *load)
+        .map(|(i, _)| i)
+}
+
+/// The central queue of a pool with limited concurrency per worker.
+/// It hands out a limited number of [`QueuePermit`]s by priority and,
+/// within a priority, in the order they were requested.
+pub(super) struct TaskQueue {
+    /// The total number of permits.
+    permits: Cell<usize>,
+    /// The number of permits that are currently available.
+    available: Cell<usize>,
+    /// The number of permits that are withdrawn once they are returned,
+    /// after the number of permits has been reduced.
+    withdrawn: Cell<usize>,
+    /// The interval after which a waiting task is raised by one priority level.
+    aging: Option<Duration>,
+    /// The maximum number of waiting tasks for [`TaskQueue::try_acquire`].
+    max_queued: usize,
+    /// The tasks waiting for a permit, in the order they were queued.
+    waiting: RefCell<VecDeque<QueuedTask>>,
+}
+
+/// A task waiting in the [`TaskQueue`].
+struct QueuedTask {
+    priority: Priority,
+    /// The time the task was queued at, in milliseconds since the epoch.
+    queued_at: f64,
+    sender: oneshot::Sender<QueuePermit>,
+}
+
+impl TaskQueue {
+    /// Creates a queue with the given number of permits.
+    fn new(permits: usize, aging: Option<Duration>, max_queued: usize) -> Self {
+        Self {
+            permits: Cell::new(permits),
+            available: Cell::new(permits),
+            withdrawn: Cell::new(0),
+            aging,
+            max_queued,
+            waiting: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Waits for a permit.
+    async fn acquire(queue: &Rc<Self>, priority: Priority) -> QueuePermit {
+        let available = queue.available.get();
+        if available > 0 && queue.waiting.borrow().is_empty() {
+            queue.available.set(available - 1);
+            return QueuePermit {
+                queue: Rc::clone(queue),
+            };
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        queue.waiting.borrow_mut().push_back(QueuedTask {
+            priority,
+            queued_at: queue.aging.map_or(0.0, |_| js_sys::Date::now()),
+            sender,
+        });
+        // The pool outlives all of its waiting tasks.
+        receiver.await.expect("Task queue dropped")
+    }
+
+    /// Waits for a permit, unless [`TaskQueue::max_queued`] tasks are waiting already.
+    async fn try_acquire(queue: &Rc<Self>, priority: Priority) -> Result<QueuePermit, Full> {
+        let waiting = queue.num_waiting();
+        let available = queue.available.get() > 0 && waiting == 0;
+        if !available && waiting >= queue.max_queued {
+            return Err(Full);
+        }
+        Ok(Self::acquire(queue, priority).await)
+    }
+
+    /// Returns the number of tasks that are still waiting for a permit.
+    fn num_waiting(&self) -> usize {
+        self.waiting
+            .borrow()
+            .iter()
+            .filter(|task| !task.sender.is_closed())
+            .count()
+    }
+
+    /// Changes the total number of permits.
+    /// Permits in use are withdrawn once they are returned.
+    fn set_permits(queue: &Rc<Self>, permits: usize) {
+        let current = queue.permits.replace(permits);
+        if permits < current {
+            let removed = current - permits;
+            let available = queue.available.get().min(removed);
+            queue.available.set(queue.available.get() - available);
+            queue
+                .withdrawn
+                .set(queue.withdrawn.get() + removed - available);
+        } else {
+            let added = permits - current;
+            let withdrawn = queue.withdrawn.get().min(added);
+            queue.withdrawn.set(queue.withdrawn.get() - withdrawn);
+            for _ in withdrawn..added {
+                Self::release(queue);
+            }
+        }
+    }
+
+    /// Passes a returned permit on to the next waiting task.
+    fn release(queue: &Rc<Self>) {
+        let withdrawn = queue.withdrawn.get();
+        if withdrawn > 0 {
+            queue.withdrawn.set(withdrawn - 1);
+            return;
+        }
+        let next = queue.next_task();
+        match next {
+            // If the task stopped waiting, the permit is dropped
+            // and passed on to the task after it.
+            Some(task) => {
+                let _ = task.sender.send(QueuePermit {
+                    queue: Rc::clone(queue),
+                });
+            }
+            None => queue.available.set(queue.available.get() + 1),
+        }
+    }
+
+    /// Removes the earliest queued task with the highest (aged) priority.
+    fn next_task(&self) -> Option<QueuedTask> {
+        let mut waiting = self.waiting.borrow_mut();
+        let now = self.aging.map_or(0.0, |_| js_sys::Date::now());
+        let levels = waiting
+            .iter()
+            .map(|task| self.level(task, now))
+            .collect::<Vec<_>>();
+        let highest = levels.iter().max()?;
+        let index = levels.iter().position(|level| level == highest)?;
+        waiting.remove(index)
+    }
+
+    /// Returns the priority level of a task, including the levels gained by waiting.
+    /// Aged tasks are raised to [`Priority::High`] at most.
+    fn level(&self, task: &QueuedTask, now: f64) -> u8 {
+        let level = task.priority as u8;
+        let Some(aging) = self.aging else {
+            return level;
+        };
+        let gained = (now - task.queued_at) / aging.as_millis().max(1) as f64;
+        level.max((level as f64 + gained).min(Priority::High as u8 as f64) as u8)
+    }
+}
+
+/// A permit to run a task in a pool with limited concurrency per worker.
+/// Dropping the permit lets the next task in the queue run.
+pub(crate) struct QueuePermit {
+    queue: Rc<TaskQueue>,
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        TaskQueue::release(&self.queue);
+    }
+}
@@ -0,0 +1,70 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A snapshot of the state and statistics of a [`super::WebWorkerPool`],
+/// as returned by [`super::WebWorkerPool::stats`].
+///
+/// Task statistics only cover tasks that have been sent to a worker.
+/// Tasks that were cancelled before their worker responded count as neither completed nor failed.
+///
+/// The snapshot can also be accessed from JavaScript:
+/// ```js
+/// let stats = await workerPoolStats();
+/// console.log(stats.in_flight_tasks, stats.functions.map((f) => f.p95_ms));
+/// ```
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PoolStats {
+    /// The state of each worker slot.
+    pub slots: Vec<SlotState>,
+    /// The number of tasks waiting in the pool's queue.
+    pub queued_tasks: usize,
+    /// The number of tasks that have been sent to a worker and have not finished yet.
+    pub in_flight_tasks: usize,
+    /// The number of tasks that have completed successfully.
+    pub completed_tasks: usize,
+    /// The number of tasks that have failed, including those whose worker has been terminated.
+    pub failed_tasks: usize,
+    /// The number of workers that have been created, including recreated ones.
+    pub workers_created: usize,
+    /// The number of workers that have been terminated, e.g., because they were idle or crashed.
+    pub workers_terminated: usize,
+    /// The total size of the task arguments sent to workers in bytes.
+    pub bytes_sent: u64,
+    /// The total size of the task results received from workers in bytes.
+    pub bytes_received: u64,
+    /// The statistics per function, sorted by function name.
+    pub functions: Vec<FunctionStats>,
+}
+
+/// The state of a worker slot in a [`PoolStats`] snapshot.
+#[non_exhaustive] // forward compatibility
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlotState {
+    /// The worker is active and can accept tasks.
+    Active,
+    /// The worker is being created.
+    Creating,
+    /// The slot has no worker, e.g., because it was terminated after being idle.
+    /// A worker is created once the slot receives a task.
+    Empty,
+}
+
+/// The statistics of a single function in a [`PoolStats`] snapshot.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct FunctionStats {
+    /// The name of the function.
+    pub name: String,
+    /// The number of tasks of this function that have completed successfully.
+    pub completed_tasks: usize,
+    /// The number of tasks of this function that have failed.
+    pub failed_tasks: usize,
+    /// The average run time of the completed tasks in milliseconds,
+    /// measured from sending the task to receiving its result.
+    pub average_ms: f64,
+    /// The 95th percentile of the run time of the most recent completed tasks in milliseconds.
+    pub p95_ms: f64,
+}
@@ -0,0 +1,121 @@
+use std::pin::pin;
+
+use futures::{
+    future::{select, Either},
+    stream, Stream, StreamExt,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    channel::Channel,
+    channel_task::{ChannelTask, ResultReceiver, TaskGuards},
+    convert::try_from_bytes,
+    error::TaskError,
+};
+
+/// The capacity of the bounded channel used for streams,
+/// i.e., the number of items a worker may send before the main thread has consumed them.
+pub(crate) const STREAM_WINDOW: u32 = 16;
+
+/// Forwards the items of the stream returned by a `#[webworker_stream_fn]` to the main thread.
+///
+/// Each item is sent as `Some(item)`, followed by `None` once the stream has ended.
+/// Since the channel is bounded, at most [`STREAM_WINDOW`] items are unconsumed at any time.
+/// Forwarding stops early if the stream is dropped on the main thread.
+#[doc(hidden)]
+pub async fn forward_stream<S>(stream: S, channel: Channel)
+where
+    S: Stream,
+    S::Item: Serialize,
+{
+    let mut stream = pin!(stream);
+    loop {
+        let item = match select(stream.next(), pin!(channel.closed())).await {
+            Either::Left((Some(item), _)) => item,
+            Either::Left((None, _)) => break,
+            Either::Right(_) => return,
+        };
+
+        // The stream has been dropped on the main thread.
+        if channel.send_async(&Some(item)).await.is_err() {
+            return;
+        }
+    }
+    let _ = channel.send_async(&None::<S::Item>).await;
+}
+
+/// The state of a stream on the main thread.
+struct StreamState {
+    channel: Channel,
+    /// The receiver for the task's result, until it has succeeded.
+    result_rx: Option<ResultReceiver>,
+    /// Keeps the task registered with the worker and queued in the pool while the stream is alive.
+    _guards: TaskGuards,
+    /// Whether the worker has stopped sending items.
+    finished: bool,
+}
+
+impl StreamState {
+    /// Receives the next item, or returns `None` once the stream has ended.
+    async fn next<R: DeserializeOwned>(&mut self) -> Option<Result<R, TaskError>> {
+        loop {
+            let recv = pin!(self.channel.recv_bytes());
+            let bytes = match self.result_rx.as_mut() {
+                Some(result_rx) => match select(recv, result_rx).await {
+                    Either::Left((bytes, _)) => bytes,
+                    // The end of the stream is still on its way over the channel.
+                    Either::Right((Ok(Ok(_)), _)) => {
+                        self.result_rx = None;
+                        continue;
+                    }
+                    Either::Right((Ok(Err(e)), _)) => return Some(Err(e)),
+                    Either::Right((Err(_), _)) => return Some(Err(TaskError::WorkerTerminated)),
+                },
+                None => recv.await,
+            };
+
+            // The channel is only closed if the task has been cancelled.
+            let bytes = bytes?;
+            return match try_from_bytes::<Option<R>>(&bytes) {
+                Ok(Some(item)) => Some(Ok(item)),
+                Ok(None) => None,
+                Err(e) => Some(Err(e)),
+            };
+        }
+    }
+}
+
+impl Drop for StreamState {
+    fn drop(&mut self) {
+        // Stop the worker from producing further items.
+        if !self.finished {
+            self.channel.close();
+        }
+    }
+}
+
+/// Turns a channel task running a `#[webworker_stream_fn]` into a stream of its items.
+///
+/// The stream ends once the worker's stream has ended or after the first error.
+pub(crate) fn into_stream<R: DeserializeOwned>(
+    task: ChannelTask<()>,
+) -> impl Stream<Item = Result<R, TaskError>> {
+    let (channel, result_rx, guards) = task.into_parts();
+    let state = StreamState {
+        channel,
+        result_rx: Some(result_rx),
+        _guards: guards,
+        finished: false,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if state.finished {
+            return None;
+        }
+        let item = state.next().await;
+        if !matches!(item, Some(Ok(_))) {
+            state.finished = true;
+        }
+        Some((item?, state))
+    })
+}
@@ -0,0 +1,63 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use tokio::sync::oneshot;
+use wasm_bindgen::{prelude::Closure, JsCast, UnwrapThrowExt};
+use web_sys::window;
+
+/// A future that resolves once the given duration has elapsed.
+/// The underlying `setTimeout` is cleared if the timer is dropped early.
+pub(crate) struct Timer {
+    /// The timeout ID (for clearTimeout on Drop).
+    id: i32,
+    /// The setTimeout closure (prevent GC).
+    _callback: Closure<dyn FnMut()>,
+    /// Resolves once the callback has been called.
+    fired: oneshot::Receiver<()>,
+}
+
+impl Timer {
+    /// Starts a new timer with the given duration.
+    pub(crate) fn new(duration: Duration) -> Self {
+        let (tx, fired) = oneshot::channel();
+        let mut tx = Some(tx);
+        let callback = Closure::<dyn FnMut()>::new(move || {
+            if let Some(tx) = tx.take() {
+                let _ = tx.send(());
+            }
+        });
+        let id = window()
+            .expect_throw("Window missing")
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                callback.as_ref().unchecked_ref(),
+                duration.as_millis().min(i32::MAX as u128) as i32,
+            )
+            .expect_throw("Could not set timeout");
+
+        Self {
+            id,
+            _callback: callback,
+            fired,
+        }
+    }
+}
+
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.fired).poll(cx).map(|_| ())
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(w) = window() {
+            w.clear_timeout_with_handle(self.id);
+        }
+    }
+}
@@ -0,0 +1,411 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{Debug, Write},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Once,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    subscriber::Interest,
+    Event, Level, Metadata, Span, Subscriber,
+};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use web_sys::MessagePort;
+
+use super::TraceContext;
+
+/// A span operation or an event inside a worker function, which is forwarded to the main thread.
+/// Spans are identified by their id inside the worker.
+#[derive(Serialize, Deserialize)]
+pub(crate) enum TraceRecord {
+    /// A span has been created.
+    NewSpan {
+        span: u32,
+        /// The parent span, or `None` if the span is a child of the task span.
+        parent: Option<u32>,
+        /// The level of the span, from `0` (error) to `4` (trace).
+        level: u8,
+        /// The target of the span inside the worker.
+        target: String,
+        /// The name of the span inside the worker.
+        name: String,
+        /// The formatted fields of the span.
+        fields: String,
+    },
+    /// Values have been recorded for the fields of a span.
+    Record { span: u32, fields: String },
+    /// A span has been entered.
+    Enter { span: u32 },
+    /// A span has been exited.
+    Exit { span: u32 },
+    /// A span has been closed.
+    Close { span: u32 },
+    /// An event has been emitted inside the innermost entered span.
+    Event {
+        /// The level of the event, from `0` (error) to `4` (trace).
+        level: u8,
+        /// The target of the event inside the worker.
+        target: String,
+        /// The message of the event, followed by its other fields.
+        message: String,
+    },
+}
+
+/// The message forwarding a [`TraceRecord`] of a task over the task port.
+#[derive(Serialize)]
+struct Forward<'a> {
+    /// The id of the task, matching the id from the [`crate::webworker::com::Request`] object.
+    id: u32,
+    trace: &'a TraceRecord,
+}
+
+/// The task whose events are currently forwarded.
+struct CurrentTask {
+    port: MessagePort,
+    id: u32,
+    max_level: u8,
+}
+
+thread_local! {
+    static CURRENT_TASK: RefCell<Option<CurrentTask>> = const { RefCell::new(None) };
+    /// The reference counts of the spans created inside the worker.
+    static SPANS: RefCell<HashMap<u32, usize>> = RefCell::default();
+    /// The spans entered inside the worker, innermost last.
+    static ENTERED: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Converts a level to the representation sent between main thread and worker.
+pub(super) fn level_to_u8(level: Level) -> u8 {
+    match level {
+        Level::ERROR => 0,
+        Level::WARN => 1,
+        Level::INFO => 2,
+        Level::DEBUG => 3,
+        Level::TRACE => 4,
+    }
+}
+
+/// Starts forwarding the spans and events of a task to the main thread.
+/// The dispatcher calls this function inside the worker before running a task
+/// that has been sent with a [`TraceContext`].
+///
+/// The forwarding subscriber is installed as the worker's global default on the first call.
+/// If the worker has installed a global subscriber already, events are not forwarded.
+#[doc(hidden)]
+#[wasm_bindgen(js_name = __wasmworker_trace_begin)]
+pub fn trace_begin(port: MessagePort, id: u32, context: JsValue) {
+    let Ok(context) = serde_wasm_bindgen::from_value::<TraceContext>(context) else {
+        return;
+    };
+
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let _ = tracing::subscriber::set_global_default(ForwardingSubscriber::default());
+    });
+
+    CURRENT_TASK.with(|task| {
+        *task.borrow_mut() = Some(CurrentTask {
+            port,
+            id,
+            max_level: context.max_level,
+        })
+    });
+}
+
+/// Stops forwarding the spans and events of a task.
+/// If another task has been started in the meantime, e.g., while an async function was waiting,
+/// the spans and events of that task are still forwarded.
+#[doc(hidden)]
+#[wasm_bindgen(js_name = __wasmworker_trace_end)]
+pub fn trace_end(id: u32) {
+    CURRENT_TASK.with(|task| {
+        let mut task = task.borrow_mut();
+        if task.as_ref().is_some_and(|task| task.id == id) {
+            *task = None;
+        }
+    });
+}
+
+/// The spans of a task inside the worker, which are recreated on the main thread.
+///
+/// Since the metadata of `tracing` spans is static, all recreated spans are named `worker_span`
+/// and carry the original name, target and fields in the fields
+/// `worker_span`, `worker_target` and `fields`.
+#[derive(Default)]
+pub(crate) struct WorkerSpans {
+    /// The recreated spans together with their formatted fields.
+    spans: HashMap<u32, (Span, String)>,
+    /// The spans entered inside the worker, innermost last.
+    entered: Vec<u32>,
+}
+
+impl WorkerSpans {
+    /// Applies a forwarded record of the task with the given span.
+    ///
+    /// New spans are children of their parent span inside the worker or of the task span.
+    /// Events are re-emitted inside the innermost span entered inside the worker.
+    /// The spans are only entered for re-emitting events, so that they do not leak
+    /// into the context of the main thread while the worker function is running.
+    pub(crate) fn apply(&mut self, record: TraceRecord, task_span: &Span) {
+        match record {
+            TraceRecord::NewSpan {
+                span,
+                parent,
+                level,
+                target,
+                name,
+                fields,
+            } => {
+                let parent = self.get(parent).unwrap_or(task_span);
+                macro_rules! span_at {
+                    ($level:expr) => {
+                        tracing::span!(
+                            target: "wasmworker::worker",
+                            parent: parent,
+                            $level,
+                            "worker_span",
+                            worker_span = %name,
+                            worker_target = %target,
+                            fields = %fields
+                        )
+                    };
+                }
+                let recreated = match level {
+                    0 => span_at!(Level::ERROR),
+                    1 => span_at!(Level::WARN),
+                    2 => span_at!(Level::INFO),
+                    3 => span_at!(Level::DEBUG),
+                    _ => span_at!(Level::TRACE),
+                };
+                self.spans.insert(span, (recreated, fields));
+            }
+            TraceRecord::Record { span, fields } => {
+                if let Some((span, all_fields)) = self.spans.get_mut(&span) {
+                    if !all_fields.is_empty() {
+                        all_fields.push(' ');
+                    }
+                    all_fields.push_str(&fields);
+                    span.record("fields", tracing::field::display(&all_fields));
+                }
+            }
+            TraceRecord::Enter { span } => self.entered.push(span),
+            TraceRecord::Exit { span } => {
+                if let Some(index) = self.entered.iter().rposition(|id| *id == span) {
+                    self.entered.remove(index);
+                }
+            }
+            TraceRecord::Close { span } => {
+                self.spans.remove(&span);
+            }
+            TraceRecord::Event {
+                level,
+                target,
+                message,
+            } => {
+                let span = self.get(self.entered.last().copied()).unwrap_or(task_span);
+                let _entered = span.enter();
+                macro_rules! emit_at {
+                    ($level:expr) => {
+                        tracing::event!(
+                            target: "wasmworker::worker",
+                            $level,
+                            worker_target = %target,
+                            "{}",
+                            message
+                        )
+                    };
+                }
+                match level {
+                    0 => emit_at!(Level::ERROR),
+                    1 => emit_at!(Level::WARN),
+                    2 => emit_at!(Level::INFO),
+                    3 => emit_at!(Level::DEBUG),
+                    _ => emit_at!(Level::TRACE),
+                }
+            }
+        }
+    }
+
+    /// Returns the recreated span with the given id inside the worker.
+    fn get(&self, span: Option<u32>) -> Option<&Span> {
+        self.spans.get(&span?).map(|(span, _)| span)
+    }
+}
+
+/// Forwards a record to the main thread if a task is currently forwarded.
+fn forward(record: TraceRecord) {
+    CURRENT_TASK.with(|task| {
+        let task = task.borrow();
+        let Some(task) = task.as_ref() else {
+            return;
+        };
+        let forward = Forward {
+            id: task.id,
+            trace: &record,
+        };
+        if let Ok(msg) = serde_wasm_bindgen::to_value(&forward) {
+            // The main thread might have closed the port in the meantime.
+            let _ = task.port.post_message(&msg);
+        }
+    });
+}
+
+/// The subscriber inside the worker, which forwards the spans and events of the current task.
+#[derive(Default)]
+struct ForwardingSubscriber {
+    /// The id of the last span.
+    last_span: AtomicU32,
+}
+
+impl Subscriber for ForwardingSubscriber {
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        // Whether a callsite is enabled changes with the current task.
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        CURRENT_TASK.with(|task| {
+            task.borrow()
+                .as_ref()
+                .is_some_and(|task| level_to_u8(*metadata.level()) <= task.max_level)
+        })
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.last_span.fetch_add(1, Ordering::Relaxed) + 1;
+        SPANS.with(|spans| spans.borrow_mut().insert(id, 1));
+
+        let parent = match span.parent() {
+            Some(parent) => Some(span_id(parent)),
+            None if span.is_contextual() => {
+                ENTERED.with(|entered| entered.borrow().last().copied())
+            }
+            None => None,
+        };
+        let mut visitor = MessageVisitor::default();
+        span.record(&mut visitor);
+        forward(TraceRecord::NewSpan {
+            span: id,
+            parent,
+            level: level_to_u8(*span.metadata().level()),
+            target: span.metadata().target().to_string(),
+            name: span.metadata().name().to_string(),
+            fields: visitor.into_fields(),
+        });
+        Id::from_u64(id.into())
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut visitor = MessageVisitor::default();
+        values.record(&mut visitor);
+        forward(TraceRecord::Record {
+            span: span_id(span),
+            fields: visitor.into_fields(),
+        });
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        forward(TraceRecord::Event {
+            level: level_to_u8(*event.metadata().level()),
+            target: event.metadata().target().to_string(),
+            message: visitor.message + &visitor.fields,
+        });
+    }
+
+    fn enter(&self, span: &Id) {
+        let span = span_id(span);
+        ENTERED.with(|entered| entered.borrow_mut().push(span));
+        forward(TraceRecord::Enter { span });
+    }
+
+    fn exit(&self, span: &Id) {
+        let span = span_id(span);
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(index) = entered.iter().rposition(|id| *id == span) {
+                entered.remove(index);
+            }
+        });
+        forward(TraceRecord::Exit { span });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        SPANS.with(|spans| {
+            if let Some(refs) = spans.borrow_mut().get_mut(&span_id(span)) {
+                *refs += 1;
+            }
+        });
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let span = span_id(&span);
+        let closed = SPANS.with(|spans| {
+            let mut spans = spans.borrow_mut();
+            let Some(refs) = spans.get_mut(&span) else {
+                return false;
+            };
+            *refs -= 1;
+            if *refs > 0 {
+                return false;
+            }
+            spans.remove(&span);
+            true
+        });
+        if closed {
+            forward(TraceRecord::Close { span });
+        }
+        closed
+    }
+}
+
+/// Returns the id of a span created by the [`ForwardingSubscriber`].
+fn span_id(span: &Id) -> u32 {
+    span.into_u64() as u32
+}
+
+/// Formats the fields of an event or a span.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    /// Returns all fields of a span, including a message.
+    fn into_fields(self) -> String {
+        if self.message.is_empty() {
+            self.fields.trim_start().to_string()
+        } else {
+            format!("message={}{}", self.message, self.fields)
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::{cell::RefCell, sync::OnceLock};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+use web_sys::MessagePort;
+
+/// A log record emitted inside a worker function, which is forwarded to the main thread.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct LogRecord {
+    /// The level of the record, from `1` (error) to `5` (trace).
+    level: u8,
+    /// The target of the record inside the worker.
+    target: String,
+    /// The formatted message of the record.
+    message: String,
+}
+
+/// The message forwarding a [`LogRecord`] of a task over the task port.
+#[derive(Serialize)]
+struct Forward<'a> {
+    /// The id of the task, matching the id from the [`crate::webworker::com::Request`] object.
+    id: u32,
+    log: &'a LogRecord,
+}
+
+/// The task whose log records are currently forwarded.
+struct CurrentTask {
+    port: MessagePort,
+    id: u32,
+}
+
+thread_local! {
+    static CURRENT_TASK: RefCell<Option<CurrentTask>> = const { RefCell::new(None) };
+}
+
+/// Returns the maximum level of the log records to be forwarded from workers,
+/// or `None` if logging is disabled on the main thread.
+pub(crate) fn max_log_level() -> Option<u8> {
+    match log::max_level() {
+        LevelFilter::Off => None,
+        level => Some(level as u8),
+    }
+}
+
+/// Starts forwarding the log records of a task to the main thread.
+/// The dispatcher calls this function inside the worker before running a task
+/// that has been sent with a log level.
+///
+/// The forwarding logger is installed as the worker's logger on the first call.
+/// If the worker has installed a logger already, records are not forwarded.
+#[doc(hidden)]
+#[wasm_bindgen(js_name = __wasmworker_log_begin)]
+pub fn log_begin(port: MessagePort, id: u32, max_level: u8) {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    if !*INSTALLED.get_or_init(|| log::set_logger(&ForwardingLogger).is_ok()) {
+        return;
+    }
+
+    let max_level = LevelFilter::iter()
+        .nth(max_level as usize)
+        .unwrap_or(LevelFilter::Trace);
+    log::set_max_level(max_level);
+    CURRENT_TASK.with(|task| *task.borrow_mut() = Some(CurrentTask { port, id }));
+}
+
+/// Stops forwarding the log records of a task.
+/// If another task has been started in the meantime, e.g., while an async function was waiting,
+/// the records of that task are still forwarded.
+#[doc(hidden)]
+#[wasm_bindgen(js_name = __wasmworker_log_end)]
+pub fn log_end(id: u32) {
+    CURRENT_TASK.with(|task| {
+        let mut task = task.borrow_mut();
+        if task.as_ref().is_some_and(|task| task.id == id) {
+            *task = None;
+        }
+    });
+}
+
+/// Re-emits a forwarded log record on the main thread,
+/// prefixed with the ids of the worker and the task.
+pub(crate) fn emit_log(record: &LogRecord, worker_id: usize, task_id: u32) {
+    let Some(level) = Level::iter().nth((record.level as usize).saturating_sub(1)) else {
+        return;
+    };
+    if level > log::max_level() {
+        return;
+    }
+    log::logger().log(
+        &Record::builder()
+            .level(level)
+            .target(&record.target)
+            .args(format_args!(
+                "[worker {worker_id}, task {task_id}] {}",
+                record.message
+            ))
+            .build(),
+    );
+}
+
+/// The logger inside the worker, which forwards the records of the current task.
+struct ForwardingLogger;
+
+impl Log for ForwardingLogger {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        CURRENT_TASK.with(|task| task.borrow().is_some())
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        CURRENT_TASK.with(|task| {
+            let task = task.borrow();
+            let Some(task) = task.as_ref() else {
+                return;
+            };
+
+            let record = LogRecord {
+                level: record.level() as u8,
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+            };
+            let forward = Forward {
+                id: task.id,
+                log: &record,
+            };
+            if let Ok(msg) = serde_wasm_bindgen::to_value(&forward) {
+                // The main thread might have closed the port in the meantime.
+                let _ = task.port.post_message(&msg);
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
@@ -0,0 +1,65 @@
+//! Diagnostics of the crate, which are emitted via [`tracing`](https://docs.rs/tracing)
+//! if the `tracing` feature is enabled, and via [`log`] otherwise.
+//!
+//! With the `tracing` feature, every task sent to a worker gets a `webworker_task` span
+//! with the fields `func_name`, `task_id` and `worker_id`, which is a child of the caller's span.
+//! Spans and events created inside the worker function are forwarded to the main thread
+//! over the task port and recreated below the task's span with the target `wasmworker::worker`.
+//!
+//! Independently of the feature, workers of a pool with
+//! [`crate::pool::WorkerPoolOptions::forward_logs`] forward the records of the [`log`] crate
+//! to the main thread, where they are re-emitted with the ids of the worker and the task.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "tracing")]
+mod forward;
+mod logger;
+#[cfg(feature = "tracing")]
+pub(crate) use forward::{TraceRecord, WorkerSpans};
+pub(crate) use logger::{emit_log, max_log_level, LogRecord};
+
+/// Emits a debug message.
+macro_rules! trace_debug {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)+);
+        #[cfg(not(feature = "tracing"))]
+        log::debug!($($arg)+);
+    }};
+}
+
+/// Emits a warning.
+macro_rules! trace_warn {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::warn!($($arg)+);
+        #[cfg(not(feature = "tracing"))]
+        log::warn!($($arg)+);
+    }};
+}
+
+pub(crate) use trace_debug;
+pub(crate) use trace_warn;
+
+/// The trace context sent to the worker with a task.
+/// If it is set, the worker forwards the spans and events of the task.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TraceContext {
+    /// The most verbose level the main thread is interested in,
+    /// from `0` (error) to `4` (trace).
+    pub(crate) max_level: u8,
+}
+
+/// Returns the trace context for the given task span,
+/// or `None` if no subscriber is interested in the span.
+#[cfg(feature = "tracing")]
+pub(crate) fn context(span: &tracing::Span) -> Option<TraceContext> {
+    if span.is_disabled() {
+        return None;
+    }
+    let max_level = tracing::level_filters::LevelFilter::current().into_level()?;
+    Some(TraceContext {
+        max_level: forward::level_to_u8(max_level),
+    })
+}
@@ -1,4 +1,13 @@
+use js_sys::Uint8Array;
 use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+
+#[cfg(feature = "tracing")]
+use crate::trace::TraceRecord;
+use crate::{
+    error::TaskError,
+    trace::{LogRecord, TraceContext},
+};
 
 /// Message sent by the worker after initialization.
 /// This is used to alert the main thread that initialization is complete.
@@ -11,6 +20,9 @@ pub(super) struct PostInit {
     /// It contains a description of the error that occurred.
     #[serde(default)]
     pub(crate) message: Option<String>,
+    /// Whether the error occurred in the init function rather than during the import.
+    #[serde(default)]
+    pub(crate) init_failed: bool,
 }
 
 /// This message is sent to the worker when a new task should be executed.
@@ -24,10 +36,67 @@ pub(super) struct Request {
     /// Whether this is a channel function (uses `__webworker_channel_` prefix).
     #[serde(default)]
     pub(crate) is_channel: bool,
-    /// The serialized argument to be passed to the function.
-    /// Serialization is done using [`crate::convert::to_bytes`].
-    #[serde(with = "serde_bytes")]
-    pub(crate) arg: Box<[u8]>,
+    /// Whether the raw bytes are passed to the function without (de)serialization
+    /// (uses `__webworker_transfer_` prefix).
+    #[serde(default)]
+    pub(crate) is_transfer: bool,
+    /// The capacity of a bounded channel, if the channel function uses one.
+    #[serde(default)]
+    pub(crate) capacity: Option<u32>,
+    /// Whether the worker should send a [`Started`] message before running the function.
+    #[serde(default)]
+    pub(crate) notify_start: bool,
+    /// If set, the worker forwards the spans and events of the function
+    /// as [`Forwarded`] messages (only with the `tracing` feature).
+    #[serde(default)]
+    pub(crate) trace: Option<TraceContext>,
+    /// If set, the worker forwards the log records up to this level
+    /// as [`ForwardedLog`] messages.
+    #[serde(default)]
+    pub(crate) log_level: Option<u8>,
+    /// The argument to be passed to the function as a `Uint8Array`.
+    /// Unless `is_transfer` is set, it is serialized using [`crate::convert::to_bytes`].
+    /// Its buffer is transferred to the worker instead of being copied.
+    #[serde(with = "serde_wasm_bindgen::preserve")]
+    pub(crate) arg: JsValue,
+}
+
+/// This message is sent to the worker when the main thread is no longer
+/// interested in the result of a task, e.g., because it has been cancelled.
+/// The worker will not send a [`Response`] for the task anymore.
+#[derive(Serialize)]
+pub(super) struct Cancel {
+    /// The id of the task to be cancelled, matching the id from the [`Request`] object.
+    pub(crate) cancel: u32,
+}
+
+/// This message is sent by the worker when it starts running a task,
+/// if requested via [`Request::notify_start`].
+#[derive(Deserialize)]
+pub(super) struct Started {
+    /// The id of the task, matching the id from the [`Request`] object.
+    pub(crate) started: u32,
+}
+
+/// This message is sent by the worker for each span operation and event of a task,
+/// if requested via [`Request::trace`].
+#[cfg(feature = "tracing")]
+#[derive(Deserialize)]
+pub(super) struct Forwarded {
+    /// The id of the task, matching the id from the [`Request`] object.
+    pub(crate) id: u32,
+    /// The span operation or event of the task.
+    pub(crate) trace: TraceRecord,
+}
+
+/// This message is sent by the worker for each log record of a task,
+/// if requested via [`Request::log_level`].
+#[derive(Deserialize)]
+pub(super) struct ForwardedLog {
+    /// The id of the task, matching the id from the [`Request`] object.
+    pub(crate) id: u32,
+    /// The log record of the task.
+    pub(crate) log: LogRecord,
 }
 
 /// This message is sent back from the worker once a task is completed,
@@ -36,9 +105,46 @@ pub(super) struct Request {
 pub(super) struct Response {
     /// The corresponding task id, matching the original id from the [`Request`] object.
     pub(crate) id: u32,
-    /// The response, which should only be `None` if the function could not be found.
+    /// The response as a `Uint8Array`, which should only be `null` if the function could not be found.
     /// This should never be the case if the [`crate::func::WebWorkerFn`] was constructed
     /// using the [`crate::webworker!`] macro.
-    #[serde(with = "serde_bytes")]
-    pub(crate) response: Option<Vec<u8>>,
+    /// Its buffer is transferred back from the worker instead of being copied.
+    #[serde(with = "serde_wasm_bindgen::preserve", default)]
+    pub(crate) response: JsValue,
+    /// This is only set if the function panicked inside the worker.
+    #[serde(default)]
+    pub(crate) panic: Option<WorkerPanic>,
+    /// Whether the wasm instance trapped, which leaves the worker in a broken state.
+    #[serde(default)]
+    pub(crate) trapped: bool,
+}
+
+impl Response {
+    /// Converts the response into the result of the function
+    /// or the corresponding [`TaskError`].
+    pub(crate) fn into_result(self, func_name: &'static str) -> Result<Uint8Array, TaskError> {
+        if let Some(panic) = self.panic {
+            return Err(TaskError::Panic {
+                message: panic.message,
+                location: panic.location,
+            });
+        }
+        if self.response.is_null() || self.response.is_undefined() {
+            return Err(TaskError::FunctionNotExported(func_name));
+        }
+        self.response
+            .dyn_into()
+            .map_err(|_| TaskError::Deserialization("Response is not a Uint8Array".to_string()))
+    }
+}
+
+/// The panic information sent back from the worker if a function panicked.
+/// It is recorded by the panic hook in [`crate::panic`].
+#[derive(Serialize, Deserialize)]
+pub(super) struct WorkerPanic {
+    /// The panic message.
+    pub(crate) message: String,
+    /// The source location of the panic, if known.
+    #[serde(default)]
+    pub(crate) location: Option<String>,
 }
@@ -12,23 +12,27 @@ use wasm_bindgen::prelude::wasm_bindgen;
 /// with task dispatch, and messages posted by the module on the global
 /// scope never reach wasmworker's response callback.
 ///
-/// `{{wasm}}` will be replaced later by an actual path.
+/// `{{wasm}}` will be replaced later by an actual path,
+/// `{{init}}` by [`INIT_JS`] and `{{dispatch}}` by [`DISPATCH_JS`].
 pub(crate) const WORKER_JS: &str = r#"
-console.debug('Initializing worker');
+
+{{init}}
+
+{{dispatch}}
 
 // Capture the dedicated task port before any module code can run.
 const portPromise = new Promise(resolve => {
     const initListener = event => {
         if (event.data && event.data.type === 'init_port') {
             self.removeEventListener('message', initListener);
-            resolve(event.ports[0]);
+            resolve({ port: event.ports[0], init: event.data.init });
         }
     };
     self.addEventListener('message', initListener);
 });
 
 (async () => {
-    const port = await portPromise;
+    const { port, init } = await portPromise;
 
     let mod;
     try {
@@ -40,14 +44,96 @@ const portPromise = new Promise(resolve => {
     }
 
     await mod.default({{wasm_bg}});
+    if (!await runInit(mod, init, port)) {
+        return;
+    }
     port.postMessage({ success: true });
-    console.debug('Worker started');
+
+    dispatch(mod, port);
+})();
+"#;
+
+/// The runner of the init function shared by [`WORKER_JS`] and [`WORKER_JS_WITH_PRECOMPILED`].
+/// It is inserted in place of `{{init}}`.
+///
+/// If the init message carries an `init` object, its function is run once
+/// right after the module has been initialized and before the worker reports
+/// success. If the function is not exported or panics, the worker reports
+/// the failure with `init_failed` set instead.
+pub(crate) const INIT_JS: &str = r#"
+const runInit = async (mod, init, port) => {
+    if (!init) {
+        return true;
+    }
+
+    const fn = mod[`__webworker_${init.func_name}`];
+    let message;
+    if (!fn) {
+        message = `Function '${init.func_name}' is not exported.`;
+    } else {
+        try {
+            await fn(init.arg);
+            return true;
+        } catch (e) {
+            const panic = self.__wasmworker_panic ?? { message: String(e) };
+            self.__wasmworker_panic = undefined;
+            message = `Function '${init.func_name}' panicked: ${panic.message}`;
+        }
+    }
+    console.error(`Unable to run init function: ${message}`);
+    port.postMessage({ success: false, init_failed: true, message: message });
+    return false;
+};
+"#;
+
+/// The task dispatcher shared by [`WORKER_JS`] and [`WORKER_JS_WITH_PRECOMPILED`].
+/// It is inserted in place of `{{dispatch}}` and handles all task requests
+/// arriving on the dedicated task port once the module has been initialized.
+///
+/// If a function panics, the panic hook installed by the generated wrapper
+/// (see [`crate::panic`]) records the panic message and location in
+/// `self.__wasmworker_panic` before the wasm instance traps. The dispatcher
+/// catches the resulting exception and reports the panic back to the main
+/// thread, so that the task does not hang forever. It also reports whether
+/// the wasm instance trapped, in which case the worker is no longer usable.
+///
+/// Arguments and results are `Uint8Array`s, whose buffers are transferred
+/// instead of being copied. Raw byte functions without (de)serialization
+/// are exported with the `__webworker_transfer_` prefix.
+///
+/// Channel functions additionally receive the transferred port and,
+/// for bounded channels, the channel's capacity.
+///
+/// If requested via `notify_start`, the dispatcher sends a `{ started: id }`
+/// message right before running the function.
+///
+/// If the request carries a `trace` context, the dispatcher lets the module
+/// forward the `tracing` spans and events of the function as `{ id, trace }` messages
+/// while it runs (see [`crate::trace`]). Modules built without the `tracing`
+/// feature do not export the hooks, so nothing is forwarded.
+/// Likewise, if the request carries a `log_level`, the module forwards
+/// the records of the `log` crate as `{ id, log }` messages.
+///
+/// When the main thread cancels a task, it sends a `{ cancel: id }` message.
+/// The dispatcher cannot interrupt a running function, but it drops the
+/// result of the cancelled task instead of sending it back.
+pub(crate) const DISPATCH_JS: &str = r#"
+const dispatch = (mod, port) => {
+    const running = new Set();
+    const cancelled = new Set();
 
     port.onmessage = async event => {
-        console.debug('Received worker event');
-        const { id, func_name, is_channel, arg } = event.data;
+        if (event.data.cancel !== undefined) {
+            if (running.has(event.data.cancel)) {
+                cancelled.add(event.data.cancel);
+            }
+            return;
+        }
+        const { id, func_name, is_channel, is_transfer, capacity, notify_start, trace, log_level, arg } = event.data;
 
-        const prefix = is_channel ? '__webworker_channel_' : '__webworker_';
+        const prefix = is_channel
+            ? '__webworker_channel_'
+            : is_transfer ? '__webworker_transfer_' : '__webworker_';
         const webworker_func_name = `${prefix}${func_name}`;
         const fn = mod[webworker_func_name];
         if (!fn) {
@@ -56,13 +142,56 @@ const portPromise = new Promise(resolve => {
             return;
         }
 
-        const worker_result = await fn(arg, event.ports[0]);
+        let worker_result;
+        running.add(id);
+        if (notify_start) {
+            port.postMessage({ started: id });
+        }
+        if (trace) {
+            mod.__wasmworker_trace_begin?.(port, id, trace);
+        }
+        if (log_level) {
+            mod.__wasmworker_log_begin?.(port, id, log_level);
+        }
+        try {
+            worker_result = await fn(arg, event.ports[0], capacity);
+        } catch (e) {
+            // The instance may have trapped, in which case it cannot be called anymore.
+            try {
+                if (trace) {
+                    mod.__wasmworker_trace_end?.(id);
+                }
+                if (log_level) {
+                    mod.__wasmworker_log_end?.(id);
+                }
+            } catch {}
+            running.delete(id);
+            cancelled.delete(id);
+            const panic = self.__wasmworker_panic ?? { message: String(e), location: null };
+            self.__wasmworker_panic = undefined;
+            const trapped = e instanceof WebAssembly.RuntimeError;
+            console.error(`Function '${func_name}' panicked: ${panic.message}`);
+            port.postMessage({ id: id, response: null, panic: panic, trapped: trapped });
+            return;
+        }
+        if (trace) {
+            mod.__wasmworker_trace_end?.(id);
+        }
+        if (log_level) {
+            mod.__wasmworker_log_end?.(id);
+        }
+        running.delete(id);
+
+        // Nobody is waiting for the result of a cancelled task.
+        if (cancelled.delete(id)) {
+            return;
+        }
 
         // Send response back to be handled by callback in main thread.
-        console.debug('Send worker result');
-        port.postMessage({ id: id, response: worker_result });
+        const transfer = worker_result instanceof Uint8Array ? [worker_result.buffer] : [];
+        port.postMessage({ id: id, response: worker_result }, transfer);
     };
-})();
+};
 "#;
 
 /// This function normally returns the path of our wasm-bindgen glue file.
@@ -82,7 +211,10 @@ pub(crate) fn main_js() -> JsString {
 /// Like [`WORKER_JS`], all wasmworker traffic runs over a dedicated
 /// `MessageChannel` port, which arrives with the `wasm_module` init message.
 pub(crate) const WORKER_JS_WITH_PRECOMPILED: &str = r#"
-console.debug('Initializing worker with pre-compiled WASM');
+
+{{init}}
+
+{{dispatch}}
 
 let mod = null;
 let initHandler = null;
@@ -92,7 +224,6 @@ initHandler = async function(event) {
     const data = event.data;
 
     if (data.type === 'wasm_module') {
-        console.debug('Received pre-compiled WASM module');
         const port = event.ports[0];
 
         // Remove this listener before running module code, so wasmworker
@@ -103,34 +234,18 @@ initHandler = async function(event) {
         try {
             mod = await import('{{wasm}}');
             await mod.default({ module_or_path: data.module });
-            port.postMessage({ success: true });
-            console.debug('Worker started with pre-compiled WASM');
         } catch (e) {
             console.error('Unable to initialize with pre-compiled WASM', e);
             port.postMessage({ success: false, message: e.toString() });
             return;
         }
+        if (!await runInit(mod, data.init, port)) {
+            return;
+        }
+        port.postMessage({ success: true });
 
         // Add the main message handler for tasks
-        port.onmessage = async event => {
-            console.debug('Received worker event');
-            const { id, func_name, is_channel, arg } = event.data;
-
-            const prefix = is_channel ? '__webworker_channel_' : '__webworker_';
-            const webworker_func_name = `${prefix}${func_name}`;
-            const fn = mod[webworker_func_name];
-            if (!fn) {
-                console.error(`Function '${func_name}' is not exported.`);
-                port.postMessage({ id: id, response: null });
-                return;
-            }
-
-            const worker_result = await fn(arg, event.ports[0]);
-
-            // Send response back to be handled by callback in main thread.
-            console.debug('Send worker result');
-            port.postMessage({ id: id, response: worker_result });
-        };
+        dispatch(mod, port);
     }
 };
 
@@ -1,13 +1,18 @@
 use std::{
     cell::{Cell, RefCell},
     collections::HashMap,
+    future::Future,
     rc::Rc,
-    sync::atomic::{AtomicU32, Ordering},
+    time::Duration,
 };
 
 use super::com::*;
 use super::js::*;
-use js_sys::Array;
+use futures::{
+    future::{select, Either},
+    Stream, StreamExt,
+};
+use js_sys::{Array, Uint8Array};
 use serde::{Deserialize, Serialize};
 use tokio::sync::{oneshot, Semaphore};
 use wasm_bindgen::{prelude::Closure, JsCast, JsValue, UnwrapThrowExt};
@@ -17,16 +22,39 @@ use web_sys::{
 };
 
 use crate::{
+    abort::{abortable, AbortHandle},
     channel::Channel,
     channel_task::ChannelTask,
-    convert::{from_bytes, to_bytes},
-    error::{Full, InitError},
-    func::{WebWorkerChannelFn, WebWorkerFn},
+    convert::{try_to_bytes, Codec},
+    error::{Full, InitError, TaskError, TryRunError},
+    func::{WebWorkerChannelFn, WebWorkerFn, WebWorkerStreamFn, WorkerInit},
+    metrics::{Metrics, TaskRecord},
+    observer::{PoolObserver, TaskInfo},
+    stream::{into_stream, STREAM_WINDOW},
+    timer::Timer,
+    trace::{trace_debug, trace_warn},
 };
 
 /// An internal type for the callback.
 type Callback = dyn FnMut(MessageEvent);
 
+/// An internal type for the map of in-flight tasks.
+type OpenTasks = Rc<RefCell<HashMap<u32, OpenTask>>>;
+
+/// A task that has been posted to the worker and has not been responded to yet.
+struct OpenTask {
+    /// The name of the function, which is reported to the [`PoolObserver`].
+    func_name: &'static str,
+    /// The channel the [`Response`] is sent out with.
+    sender: oneshot::Sender<Response>,
+    /// The span of the task, below which forwarded spans and events are recreated.
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    /// The spans created inside the worker, which have been recreated on the main thread.
+    #[cfg(feature = "tracing")]
+    worker_spans: crate::trace::WorkerSpans,
+}
+
 /// This struct represents a single web worker instance.
 /// It can be created using [`WebWorker::new`] or [`WebWorker::with_path`].
 /// When an instance of this type is dropped, it also terminates the corresponding web worker.
@@ -59,15 +87,90 @@ pub struct WebWorker {
     port: MessagePort,
     /// An optional limit on the number of tasks queued at the same time.
     task_limit: Option<Semaphore>,
-    /// The current task id, which is used to reidentify responses.
-    current_task: AtomicU32,
     /// A map between task ids and the channel they need to be sent out with.
     /// [`Response`]s will arrive on our callback and we redistribute them to their origin.
-    open_tasks: Rc<RefCell<HashMap<u32, oneshot::Sender<Response>>>>,
+    open_tasks: OpenTasks,
     /// The callback handle for the worker.
     _callback: Closure<Callback>,
+    /// The callback handle for errors reported by the worker.
+    _error_callback: Closure<dyn FnMut(JsValue)>,
     /// Timestamp (ms since epoch) of the last completed task, used for idle timeout tracking.
     last_active: Rc<Cell<f64>>,
+    /// Whether the worker has been terminated, e.g., because it crashed.
+    terminated: Rc<Cell<bool>>,
+    /// The task and worker counters, which are shared with the pool, if any.
+    /// They also hand out the task ids, which are used to reidentify responses.
+    metrics: Rc<Metrics>,
+    /// The id of the worker, which is reported to the [`PoolObserver`].
+    id: usize,
+    /// Whether the log records of tasks are forwarded to the main thread.
+    forward_logs: bool,
+}
+
+/// A cheaply cloneable handle to the parts of a [`WebWorker`]
+/// that are needed to terminate it from within callbacks.
+#[derive(Clone)]
+pub(crate) struct WorkerHandle {
+    worker: Worker,
+    port: MessagePort,
+    open_tasks: OpenTasks,
+    terminated: Rc<Cell<bool>>,
+}
+
+impl WorkerHandle {
+    /// Terminates the worker and fails all of its in-flight tasks
+    /// with [`TaskError::WorkerTerminated`].
+    pub(crate) fn terminate(&self) {
+        if self.terminated.replace(true) {
+            return;
+        }
+        self.port.close();
+        self.worker.terminate();
+
+        // Dropping the senders fails the tasks.
+        let tasks = std::mem::take(&mut *self.open_tasks.borrow_mut());
+        drop(tasks);
+    }
+
+    /// Removes an in-flight task and tells the worker to drop its result.
+    /// Does nothing if the task has already been completed.
+    pub(crate) fn cancel_task(&self, id: u32) {
+        let task = self.open_tasks.borrow_mut().remove(&id);
+        if task.is_none() || self.terminated.get() {
+            return;
+        }
+
+        if let Ok(msg) = serde_wasm_bindgen::to_value(&Cancel { cancel: id }) {
+            // The worker might have terminated in the meantime.
+            let _ = self.port.post_message(&msg);
+        }
+    }
+}
+
+/// A guard for a task that has been posted to a worker.
+/// If it is dropped before the response arrived, the task is cancelled,
+/// so that it does not linger in the worker's open tasks.
+pub(crate) struct PendingTask {
+    id: u32,
+    worker: WorkerHandle,
+}
+
+impl PendingTask {
+    /// Cancels the task, see [`WorkerHandle::cancel_task`].
+    pub(crate) fn cancel(&self) {
+        self.worker.cancel_task(self.id);
+    }
+
+    /// Returns the worker the task has been posted to.
+    pub(crate) fn worker(&self) -> &WorkerHandle {
+        &self.worker
+    }
+}
+
+impl Drop for PendingTask {
+    fn drop(&mut self) {
+        self.cancel();
+    }
 }
 
 impl WebWorker {
@@ -103,6 +206,8 @@ impl WebWorker {
         let code = Array::new();
         code.push(&JsValue::from_str(
             &worker_js
+                .replace("{{init}}", INIT_JS)
+                .replace("{{dispatch}}", DISPATCH_JS)
                 .replace("{{wasm}}", wasm_path)
                 .replace("{{wasm_bg}}", &wasm_bg_path),
         ));
@@ -143,6 +248,30 @@ impl WebWorker {
         main_bg_js: Option<&str>,
         task_limit: Option<usize>,
         wasm_module: Option<js_sys::WebAssembly::Module>,
+    ) -> Result<WebWorker, InitError> {
+        Self::with_metrics(
+            main_js,
+            main_bg_js,
+            task_limit,
+            wasm_module,
+            Rc::default(),
+            false,
+            None,
+        )
+        .await
+    }
+
+    /// Create a new [`WebWorker`] that records its tasks in the given [`Metrics`]
+    /// and optionally forwards the log records of its tasks to the main thread.
+    /// If an init function is given, the worker runs it before it reports to be ready.
+    pub(crate) async fn with_metrics(
+        main_js: Option<&str>,
+        main_bg_js: Option<&str>,
+        task_limit: Option<usize>,
+        wasm_module: Option<js_sys::WebAssembly::Module>,
+        metrics: Rc<Metrics>,
+        forward_logs: bool,
+        init: Option<&WorkerInit>,
     ) -> Result<WebWorker, InitError> {
         // Create worker
         let worker_options = WorkerOptions::new();
@@ -179,6 +308,23 @@ impl WebWorker {
             )
             .expect_throw("Could not set type");
         }
+        if let Some(init) = init {
+            let init_obj = js_sys::Object::new();
+            js_sys::Reflect::set(
+                &init_obj,
+                &JsValue::from_str("func_name"),
+                &JsValue::from_str(init.func_name),
+            )
+            .expect_throw("Could not set init function name");
+            js_sys::Reflect::set(
+                &init_obj,
+                &JsValue::from_str("arg"),
+                &Uint8Array::from(&*init.arg),
+            )
+            .expect_throw("Could not set init argument");
+            js_sys::Reflect::set(&init_msg, &JsValue::from_str("init"), &init_obj)
+                .expect_throw("Could not set init function");
+        }
 
         let transfer = Array::new();
         transfer.push(&worker_port);
@@ -199,49 +345,129 @@ impl WebWorker {
 
         // Handle errors in webworker init
         if !post_init.success {
-            return Err(InitError::WebWorkerModuleLoading(
-                post_init
-                    .message
-                    .expect_throw("Post init should have error message"),
-            ));
+            worker.terminate();
+            let message = post_init
+                .message
+                .expect_throw("Post init should have error message");
+            return Err(if post_init.init_failed {
+                InitError::InitFunction(message)
+            } else {
+                InitError::WebWorkerModuleLoading(message)
+            });
         }
 
         let tasks = Rc::new(RefCell::new(HashMap::new()));
         let last_active = Rc::new(Cell::new(js_sys::Date::now()));
+        let handle = WorkerHandle {
+            worker: worker.clone(),
+            port: port.clone(),
+            open_tasks: Rc::clone(&tasks),
+            terminated: Rc::new(Cell::new(false)),
+        };
 
-        let callback_handle = Self::callback(Rc::clone(&tasks), Rc::clone(&last_active));
+        // A worker that reports an error is considered crashed.
+        let error_handle = Self::error_callback(handle.clone());
+        worker.set_onerror(Some(error_handle.as_ref().unchecked_ref()));
+        worker.set_onmessageerror(Some(error_handle.as_ref().unchecked_ref()));
+        port.set_onmessageerror(Some(error_handle.as_ref().unchecked_ref()));
+
+        let id = metrics.worker_created();
+        trace_debug!("WebWorker {id} started");
+        let callback_handle = Self::callback(
+            handle.clone(),
+            Rc::clone(&last_active),
+            Rc::clone(&metrics),
+            id,
+        );
         port.set_onmessage(Some(callback_handle.as_ref().unchecked_ref()));
 
         Ok(WebWorker {
             worker,
             port,
             task_limit: task_limit.map(|limit| Semaphore::new(limit)),
-            current_task: AtomicU32::new(0),
             open_tasks: tasks,
             _callback: callback_handle,
+            _error_callback: error_handle,
             last_active,
+            terminated: handle.terminated,
+            metrics,
+            id,
+            forward_logs,
         })
     }
 
-    /// Function to be called when a result is ready.
+    /// Function to be called when a result is ready
+    /// or, if requested, when the worker has started running a task
+    /// or forwards a log record or event.
     fn callback(
-        tasks: Rc<RefCell<HashMap<u32, oneshot::Sender<Response>>>>,
+        handle: WorkerHandle,
         last_active: Rc<Cell<f64>>,
-    ) -> Closure<dyn FnMut(MessageEvent)> {
+        metrics: Rc<Metrics>,
+        worker_id: usize,
+    ) -> Closure<Callback> {
         Closure::new(move |event: MessageEvent| {
             let data = event.data();
+            if js_sys::Reflect::has(&data, &JsValue::from_str("started")).unwrap_or(false) {
+                let Started { started: id } = serde_wasm_bindgen::from_value(data)
+                    .expect_throw("Could not deserialize start message");
+                let func_name = handle.open_tasks.borrow().get(&id).map(|t| t.func_name);
+                if let Some(func_name) = func_name {
+                    metrics.task_started(TaskInfo {
+                        id,
+                        func_name,
+                        worker_id,
+                    });
+                }
+                return;
+            }
+
+            if js_sys::Reflect::has(&data, &JsValue::from_str("log")).unwrap_or(false) {
+                let ForwardedLog { id, log } = serde_wasm_bindgen::from_value(data)
+                    .expect_throw("Could not deserialize forwarded log record");
+                crate::trace::emit_log(&log, worker_id, id);
+                return;
+            }
+
+            #[cfg(feature = "tracing")]
+            if js_sys::Reflect::has(&data, &JsValue::from_str("trace")).unwrap_or(false) {
+                let Forwarded { id, trace } = serde_wasm_bindgen::from_value(data)
+                    .expect_throw("Could not deserialize forwarded trace record");
+                if let Some(task) = handle.open_tasks.borrow_mut().get_mut(&id) {
+                    task.worker_spans.apply(trace, &task.span);
+                }
+                return;
+            }
+
             let response: Response =
                 serde_wasm_bindgen::from_value(data).expect_throw("Could not deserialize response");
-            let mut tasks_wg = tasks.borrow_mut();
+            let trapped = response.trapped;
 
             // Send response on channel.
-            if let Some(channel) = tasks_wg.remove(&response.id) {
+            let task = handle.open_tasks.borrow_mut().remove(&response.id);
+            if let Some(task) = task {
+                #[cfg(feature = "tracing")]
+                let _entered = task.span.enter();
+                trace_debug!("WebWorker task finished");
                 // Ignore if receiver is already closed.
-                let _ = channel.send(response);
+                let _ = task.sender.send(response);
             }
 
             // Update idle tracking timestamp.
             last_active.set(js_sys::Date::now());
+
+            // The wasm instance of the worker is broken after a trap.
+            if trapped {
+                trace_warn!("WebWorker trapped, terminating it");
+                handle.terminate();
+            }
+        })
+    }
+
+    /// Function to be called when the worker reports an error.
+    fn error_callback(handle: WorkerHandle) -> Closure<dyn FnMut(JsValue)> {
+        Closure::new(move |event: JsValue| {
+            trace_warn!("WebWorker crashed, terminating it: {event:?}");
+            handle.terminate();
         })
     }
 
@@ -259,12 +485,42 @@ impl WebWorker {
     /// ```ignore
     /// worker.run(webworker!(sort_vec), &my_vec).await
     /// ```
-    pub async fn run<T, R>(&self, func: WebWorkerFn<T, R>, arg: &T) -> R
+    pub async fn run<T, R, C: Codec>(&self, func: WebWorkerFn<T, R, C>, arg: &T) -> R
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
     {
-        self.run_internal(func, arg).await
+        self.run_internal(func, arg, None)
+            .await
+            .expect_throw("WebWorker task failed")
+    }
+
+    /// This function differs from [`WebWorker::run`] by returning a [`TaskError`]
+    /// instead of panicking if the task cannot be completed,
+    /// e.g., because the function is not exported or (de)serialization fails.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// If a task limit has been set, this function will yield until previous tasks have been finished.
+    ///
+    /// Example:
+    /// ```ignore
+    /// match worker.run_checked(webworker!(sort_vec), &my_vec).await {
+    ///     Ok(sorted) => { /* ... */ }
+    ///     Err(e) => log::warn!("Task failed: {e}"),
+    /// }
+    /// ```
+    pub async fn run_checked<T, R, C: Codec>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+    ) -> Result<R, TaskError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_internal(func, arg, None).await
     }
 
     /// Run an async function with bidirectional channel support on this [`WebWorker`].
@@ -289,27 +545,175 @@ impl WebWorker {
     /// task.send(&Continue { should_continue: true });
     /// let result: ProcessResult = task.result().await;
     /// ```
-    pub async fn run_channel<T, R>(&self, func: WebWorkerChannelFn<T, R>, arg: &T) -> ChannelTask<R>
+    pub async fn run_channel<T, R, In, Out>(
+        &self,
+        func: WebWorkerChannelFn<T, R, In, Out>,
+        arg: &T,
+    ) -> ChannelTask<R, In, Out>
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
     {
-        self.run_channel_internal(func, arg).await
+        self.run_channel_internal(func.name, arg, None).await
+    }
+
+    /// This function differs from [`WebWorker::run_channel`] by bounding the channel in both directions.
+    ///
+    /// At most `capacity` messages are buffered on either side. Use `send_async` on the
+    /// [`Channel`](crate::Channel) or [`ChannelTask`] to wait until the other side has received
+    /// enough of the previous messages. This way, a worker that reports progress faster
+    /// than the main thread consumes it is slowed down instead of growing memory without bound.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let task = worker
+    ///     .run_channel_bounded(webworker_channel!(process_with_progress), &data, 16)
+    ///     .await;
+    /// ```
+    pub async fn run_channel_bounded<T, R, In, Out>(
+        &self,
+        func: WebWorkerChannelFn<T, R, In, Out>,
+        arg: &T,
+        capacity: u32,
+    ) -> ChannelTask<R, In, Out>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_channel_internal(func.name, arg, Some(capacity))
+            .await
+    }
+
+    /// Run a function producing a stream of items on this [`WebWorker`].
+    ///
+    /// The returned stream yields the items in order and ends once the worker's stream has ended.
+    /// The worker only produces a limited number of items ahead of the consumer.
+    /// Dropping the stream stops the worker from producing further items.
+    ///
+    /// The `func`: [`WebWorkerStreamFn`] argument should normally be instantiated using the
+    /// [`crate::webworker_stream!`] macro. This ensures type safety and that the function
+    /// is correctly exposed to the worker.
+    ///
+    /// If a task limit has been set, this function will yield until previous tasks have been finished.
+    /// The stream panics if the task could not be completed.
+    /// Use [`WebWorker::run_stream_checked`] for a fallible variant.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let mut items = pin!(worker.run_stream(webworker_stream!(count_to), &10).await);
+    /// while let Some(item) = items.next().await {
+    ///     // ...
+    /// }
+    /// ```
+    pub async fn run_stream<T, R>(
+        &self,
+        func: WebWorkerStreamFn<T, R>,
+        arg: &T,
+    ) -> impl Stream<Item = R>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_stream_checked(func, arg)
+            .await
+            .map(|item| item.expect_throw("WebWorker task failed"))
+    }
+
+    /// This function differs from [`WebWorker::run_stream`] by yielding a [`TaskError`]
+    /// if the task could not be completed. The stream ends after the first error.
+    pub async fn run_stream_checked<T, R>(
+        &self,
+        func: WebWorkerStreamFn<T, R>,
+        arg: &T,
+    ) -> impl Stream<Item = Result<R, TaskError>>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        into_stream(
+            self.run_channel_internal(func.name, arg, Some(STREAM_WINDOW))
+                .await,
+        )
+    }
+
+    /// This function differs from [`WebWorker::run_checked`] by failing with [`TaskError::Timeout`]
+    /// if the task does not complete within the given `timeout`.
+    /// The timeout starts once the task has been sent to the worker.
+    ///
+    /// Since a running computation cannot be interrupted otherwise,
+    /// the worker is terminated on timeout and all of its other in-flight tasks
+    /// fail with [`TaskError::WorkerTerminated`].
+    /// Afterwards, the worker cannot be used anymore (see [`WebWorker::is_terminated`]).
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// Example:
+    /// ```ignore
+    /// worker.run_with_timeout(webworker!(sort_vec), &my_vec, Duration::from_secs(1)).await
+    /// ```
+    pub async fn run_with_timeout<T, R, C: Codec>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+        timeout: Duration,
+    ) -> Result<R, TaskError>
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de>,
+    {
+        self.run_internal(func, arg, Some(timeout)).await
+    }
+
+    /// This function differs from [`WebWorker::run_checked`] by returning an [`AbortHandle`]
+    /// alongside the task's future, which can be used to cancel the task.
+    /// A cancelled task resolves to [`TaskError::Cancelled`].
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// This ensures type safety and that the function is correctly exposed to the worker.
+    ///
+    /// The task is only scheduled once the future is polled.
+    /// If a task limit has been set, the future will yield until previous tasks have been finished.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let (handle, task) = worker.run_cancellable(webworker!(sort_vec), &my_vec);
+    /// handle.abort();
+    /// assert!(matches!(task.await, Err(TaskError::Cancelled)));
+    /// ```
+    pub fn run_cancellable<'a, T, R, C: Codec>(
+        &'a self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &'a T,
+    ) -> (AbortHandle, impl Future<Output = Result<R, TaskError>> + 'a)
+    where
+        T: Serialize + for<'de> Deserialize<'de>,
+        R: Serialize + for<'de> Deserialize<'de> + 'a,
+    {
+        let (handle, registration) = AbortHandle::new_pair();
+        handle.set_worker(self.handle());
+        let task = abortable(self.run_internal(func, arg, None), registration);
+        (handle, task)
     }
 
     /// This function differs from [`WebWorker::run`] by returning early if the given task limit is reached.
-    /// In this case a [`Full`] error is returned.
+    /// In this case a [`TryRunError::Full`] error is returned.
+    /// If the task fails, e.g., because the worker panicked, a [`TryRunError::Task`] error is returned.
     ///
     /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
     /// This ensures type safety and that the function is correctly exposed to the worker.
     ///
-    /// If no task limit has been set, this function can never return an error.
+    /// If no task limit has been set, this function never returns [`TryRunError::Full`].
     ///
     /// Example:
     /// ```ignore
     /// worker.try_run(webworker!(sort_vec), &my_vec).await
     /// ```
-    pub async fn try_run<T, R>(&self, func: WebWorkerFn<T, R>, arg: &T) -> Result<R, Full>
+    pub async fn try_run<T, R, C: Codec>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+    ) -> Result<R, TryRunError>
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
@@ -331,43 +735,74 @@ impl WebWorker {
     /// ```ignore
     /// worker.run_bytes(webworker!(sort), &my_box).await
     /// ```
-    pub async fn run_bytes(
+    pub async fn run_bytes<C: Codec>(
         &self,
-        func: WebWorkerFn<Box<[u8]>, Box<[u8]>>,
+        func: WebWorkerFn<Box<[u8]>, Box<[u8]>, C>,
         arg: &Box<[u8]>,
     ) -> Box<[u8]> {
-        self.run_internal(func, arg).await
+        self.run_internal(func, arg, None)
+            .await
+            .expect_throw("WebWorker task failed")
+    }
+
+    /// This function outsources a task on a [`WebWorker`] which has `Box<[u8]>` both as input and output
+    /// without any (de)serialization or copies on the calling side.
+    /// The whole `ArrayBuffer` underlying `arg` is transferred to the worker and is detached afterwards,
+    /// i.e., neither `arg` nor any other view on that buffer can be used by the caller anymore,
+    /// even if `arg` only covers a part of it. The result is transferred back.
+    /// This is useful for large buffers, such as images.
+    ///
+    /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
+    /// Only functions taking and returning `Box<[u8]>` can be used with this method.
+    /// Otherwise, a [`TaskError::FunctionNotExported`] is returned.
+    ///
+    /// Buffers that cannot be transferred, such as views on wasm memory or already detached buffers,
+    /// fail with [`TaskError::TransferFailed`].
+    ///
+    /// If a task limit has been set, this function will yield until previous tasks have been finished.
+    ///
+    /// Example:
+    /// ```ignore
+    /// let pixels: Uint8Array = image_data.data().into();
+    /// let blurred = worker.run_transfer(webworker!(blur), pixels).await?;
+    /// ```
+    pub async fn run_transfer<C: Codec>(
+        &self,
+        func: WebWorkerFn<Box<[u8]>, Box<[u8]>, C>,
+        arg: Uint8Array,
+    ) -> Result<Uint8Array, TaskError> {
+        self.run_transfer_internal(func, arg, None).await
     }
 
     /// This function differs from [`WebWorker::run_bytes`] by returning early if the given task limit is reached.
-    /// In this case a [`Full`] error is returned.
+    /// In this case a [`TryRunError::Full`] error is returned.
     /// (De)serialization of values needs to be handled by the caller.
     /// For more convenient access, use [`WebWorker::try_run`] instead.
     ///
     /// The `func`: [`WebWorkerFn`] argument should normally be instantiated using the [`crate::webworker!`] macro.
     /// This ensures type safety and that the function is correctly exposed to the worker.
     ///
-    /// If no task limit has been set, this function can never return an error.
+    /// If no task limit has been set, this function never returns [`TryRunError::Full`].
     ///
     /// Example:
     /// ```ignore
     /// worker.try_run_bytes(webworker!(sort), &my_box).await
     /// ```
-    pub async fn try_run_bytes(
+    pub async fn try_run_bytes<C: Codec>(
         &self,
-        func: WebWorkerFn<Box<[u8]>, Box<[u8]>>,
+        func: WebWorkerFn<Box<[u8]>, Box<[u8]>, C>,
         arg: &Box<[u8]>,
-    ) -> Result<Box<[u8]>, Full> {
+    ) -> Result<Box<[u8]>, TryRunError> {
         self.try_run_internal(func, arg).await
     }
 
     /// Internal function to schedule a simple task to the worker.
     /// This variant returns early if a semaphore permit cannot be obtained immediately.
-    pub(crate) async fn try_run_internal<T, R>(
+    pub(crate) async fn try_run_internal<T, R, C: Codec>(
         &self,
-        func: WebWorkerFn<T, R>,
+        func: WebWorkerFn<T, R, C>,
         arg: &T,
-    ) -> Result<R, Full>
+    ) -> Result<R, TryRunError>
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
@@ -376,18 +811,26 @@ impl WebWorker {
         let _permit = if let Some(ref s) = self.task_limit {
             Some(match s.try_acquire() {
                 Ok(permit) => permit,
-                Err(_) => return Err(Full),
+                Err(_) => return Err(Full.into()),
             })
         } else {
             None
         };
 
         // Convert arg and result.
-        Ok(self.force_run(func.name, arg, false, None).await)
+        Ok(self
+            .force_run::<T, R, C>(func.name, arg, false, None)
+            .await?)
     }
 
     /// Internal function to schedule a simple task to the worker.
-    pub(crate) async fn run_internal<T, R>(&self, func: WebWorkerFn<T, R>, arg: &T) -> R
+    /// If a `timeout` is given, the worker is terminated once it expires.
+    pub(crate) async fn run_internal<T, R, C: Codec>(
+        &self,
+        func: WebWorkerFn<T, R, C>,
+        arg: &T,
+        timeout: Option<Duration>,
+    ) -> Result<R, TaskError>
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
@@ -400,17 +843,74 @@ impl WebWorker {
         };
 
         // Convert arg and result.
-        self.force_run(func.name, arg, false, None).await
+        self.with_timeout(
+            self.force_run::<T, R, C>(func.name, arg, false, None),
+            timeout,
+        )
+        .await
+    }
+
+    /// Internal function to schedule a task to the worker
+    /// that receives and returns raw bytes without (de)serialization.
+    pub(crate) async fn run_transfer_internal<C: Codec>(
+        &self,
+        func: WebWorkerFn<Box<[u8]>, Box<[u8]>, C>,
+        arg: Uint8Array,
+        timeout: Option<Duration>,
+    ) -> Result<Uint8Array, TaskError> {
+        // Acquire permit if necessary.
+        let _permit = if let Some(ref s) = self.task_limit {
+            Some(s.acquire().await.unwrap())
+        } else {
+            None
+        };
+
+        let id = self.metrics.next_task_id();
+        let request = Request {
+            id,
+            func_name: func.name,
+            is_channel: false,
+            is_transfer: true,
+            capacity: None,
+            notify_start: self.metrics.has_observer(),
+            trace: None,
+            log_level: None,
+            arg: arg.into(),
+        };
+        self.with_timeout(self.send_request(id, request, None), timeout)
+            .await
+    }
+
+    /// Awaits the task, but terminates the worker if the optional `timeout` expires first.
+    async fn with_timeout<R>(
+        &self,
+        task: impl Future<Output = Result<R, TaskError>>,
+        timeout: Option<Duration>,
+    ) -> Result<R, TaskError> {
+        let Some(timeout) = timeout else {
+            return task.await;
+        };
+
+        match select(std::pin::pin!(task), Timer::new(timeout)).await {
+            Either::Left((res, _)) => res,
+            Either::Right(_) => {
+                trace_warn!("WebWorker task timed out after {timeout:?}, terminating worker");
+                self.handle().terminate();
+                Err(TaskError::Timeout)
+            }
+        }
     }
 
     /// Internal function to schedule a channel task to the worker.
     /// Creates a `MessageChannel` internally, sends one port to the worker,
     /// and returns a `ChannelTask` wrapping the other port and the result future.
-    pub(crate) async fn run_channel_internal<T, R>(
+    /// If a `capacity` is given, the channel is bounded in both directions.
+    pub(crate) async fn run_channel_internal<T, R, In, Out>(
         &self,
-        func: WebWorkerChannelFn<T, R>,
+        func_name: &'static str,
         arg: &T,
-    ) -> ChannelTask<R>
+        capacity: Option<u32>,
+    ) -> ChannelTask<R, In, Out>
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
@@ -424,119 +924,231 @@ impl WebWorker {
 
         // Create the MessageChannel internally.
         let msg_channel = MessageChannel::new().expect_throw("Could not create MessageChannel");
-        let channel = Channel::from(msg_channel.port1());
+        let channel = Channel::with_capacity(msg_channel.port1(), capacity);
         let worker_port = msg_channel.port2();
 
         // Send the request and get a receiver for the result bytes.
-        let result_rx = self.send_channel_request(func.name, arg, worker_port);
+        let (result_rx, pending) = self.send_channel_request(func_name, arg, worker_port, capacity);
 
-        ChannelTask::new(channel, result_rx)
+        ChannelTask::new(channel, result_rx, pending)
     }
 
     /// This function handles the communication with the worker
     /// after the task limit has been checked.
     /// It also handles (de)serialization.
-    async fn force_run<T, R>(
+    async fn force_run<T, R, C: Codec>(
         &self,
         func_name: &'static str,
         arg: &T,
         is_channel: bool,
         port: Option<MessagePort>,
-    ) -> R
+    ) -> Result<R, TaskError>
     where
         T: Serialize + for<'de> Deserialize<'de>,
         R: Serialize + for<'de> Deserialize<'de>,
     {
-        let id = self.current_task.fetch_add(1, Ordering::Relaxed);
+        let id = self.metrics.next_task_id();
         let request = Request {
             id,
             func_name,
             is_channel,
-            arg: to_bytes(arg),
+            is_transfer: false,
+            capacity: None,
+            notify_start: self.metrics.has_observer(),
+            trace: None,
+            log_level: None,
+            arg: Uint8Array::from(&*C::encode(arg)?).into(),
         };
 
-        let res = self.send_request(id, request, port).await;
-        from_bytes(&res)
+        let res = self.send_request(id, request, port).await?;
+        C::decode(&res.to_vec())
     }
 
     /// Sends a request to the worker and waits for the response.
     /// This is extracted from `force_run` to reduce monomorphisation cost.
-    async fn send_request(&self, id: u32, request: Request, port: Option<MessagePort>) -> Vec<u8> {
-        // Create channel and add task.
-        let (sender, receiver) = oneshot::channel();
-        self.open_tasks.borrow_mut().insert(id, sender);
+    async fn send_request(
+        &self,
+        id: u32,
+        request: Request,
+        port: Option<MessagePort>,
+    ) -> Result<Uint8Array, TaskError> {
+        let func_name = request.func_name;
+        let (receiver, record) = self.post_request(id, request, port)?;
+
+        // Cancel the task if this future is dropped early.
+        let _pending = PendingTask {
+            id,
+            worker: self.handle(),
+        };
 
-        // send the task to the webworker, either with a port or without one
+        // Handle result.
+        let res = receiver
+            .await
+            .map_err(|_| TaskError::WorkerTerminated)
+            .and_then(|response| response.into_result(func_name));
+        record.finish(&res);
+        res
+    }
+
+    /// Registers the task and posts the request to the worker,
+    /// either with a port or without one.
+    /// The buffer of the argument is transferred to the worker.
+    /// Returns a receiver for the [`Response`] and the [`TaskRecord`] for its outcome.
+    fn post_request(
+        &self,
+        id: u32,
+        mut request: Request,
+        port: Option<MessagePort>,
+    ) -> Result<(oneshot::Receiver<Response>, TaskRecord), TaskError> {
+        if self.is_terminated() {
+            return Err(TaskError::WorkerTerminated);
+        }
+
+        let func_name = request.func_name;
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "webworker_task",
+            func_name,
+            task_id = id,
+            worker_id = self.id
+        );
+        #[cfg(feature = "tracing")]
+        {
+            request.trace = crate::trace::context(&span);
+        }
+        if self.forward_logs {
+            request.log_level = crate::trace::max_log_level();
+        }
+        let mut bytes_sent = 0;
+        let transfer = Array::new();
+        if let Some(arg) = request.arg.dyn_ref::<Uint8Array>() {
+            bytes_sent = arg.length();
+            transfer.push(&arg.buffer());
+        }
         if let Some(port) = port {
-            let transfer = Array::new();
             transfer.push(&port);
+        }
+        let request = serde_wasm_bindgen::to_value(&request)
+            .map_err(|e| TaskError::Serialization(e.to_string()))?;
 
-            self.port
-                .post_message_with_transferable(
-                    &serde_wasm_bindgen::to_value(&request)
-                        .expect_throw("Could not serialize request"),
-                    &transfer,
-                )
-                .expect_throw("WebWorker gone");
-        } else {
-            self.port
-                .post_message(
-                    &serde_wasm_bindgen::to_value(&request)
-                        .expect_throw("Could not serialize request"),
-                )
-                .expect_throw("WebWorker gone");
+        // Create channel and add task.
+        let (sender, receiver) = oneshot::channel();
+        self.open_tasks.borrow_mut().insert(
+            id,
+            OpenTask {
+                func_name,
+                sender,
+                #[cfg(feature = "tracing")]
+                span: span.clone(),
+                #[cfg(feature = "tracing")]
+                worker_spans: Default::default(),
+            },
+        );
+
+        let res = self
+            .port
+            .post_message_with_transferable(&request, &transfer);
+
+        if let Err(e) = res {
+            self.open_tasks.borrow_mut().remove(&id);
+            if self.is_terminated() {
+                return Err(TaskError::WorkerTerminated);
+            }
+            let message = match e.dyn_ref::<js_sys::Error>() {
+                Some(e) => e.message().into(),
+                None => format!("{e:?}"),
+            };
+            return Err(TaskError::TransferFailed(message));
         }
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        trace_debug!("WebWorker task sent");
 
-        // Handle result.
-        receiver
-            .await
-            .expect_throw("WebWorker gone")
-            .response
-            .expect_throw("Could not find function")
+        let task = TaskInfo {
+            id,
+            func_name,
+            worker_id: self.id,
+        };
+        let record = self
+            .metrics
+            .start_task(task, bytes_sent, Rc::clone(&self.terminated));
+        Ok((receiver, record))
     }
 
     /// Sends a channel request to the worker and returns a receiver for the result bytes.
     /// Unlike `send_request`, this does not await the result — it returns immediately
     /// so the caller can interact with the channel before consuming the result.
+    /// Errors are delivered through the returned receiver.
+    /// If the request has been posted, a [`PendingTask`] guard is returned as well.
     fn send_channel_request<T>(
         &self,
         func_name: &'static str,
         arg: &T,
         port: MessagePort,
-    ) -> oneshot::Receiver<Vec<u8>>
+        capacity: Option<u32>,
+    ) -> (
+        oneshot::Receiver<Result<Vec<u8>, TaskError>>,
+        Option<PendingTask>,
+    )
     where
         T: Serialize + for<'de> Deserialize<'de>,
     {
-        let id = self.current_task.fetch_add(1, Ordering::Relaxed);
-        let request = Request {
-            id,
-            func_name,
-            is_channel: true,
-            arg: to_bytes(arg),
-        };
-
-        let (sender, receiver) = oneshot::channel();
-        self.open_tasks.borrow_mut().insert(id, sender);
-
-        let transfer = Array::new();
-        transfer.push(&port);
+        let (byte_sender, byte_receiver) = oneshot::channel();
 
-        self.port
-            .post_message_with_transferable(
-                &serde_wasm_bindgen::to_value(&request).expect_throw("Could not serialize request"),
-                &transfer,
-            )
-            .expect_throw("WebWorker gone");
+        let id = self.metrics.next_task_id();
+        let receiver = try_to_bytes(arg).and_then(|arg| {
+            let request = Request {
+                id,
+                func_name,
+                is_channel: true,
+                is_transfer: false,
+                capacity,
+                notify_start: self.metrics.has_observer(),
+                trace: None,
+                log_level: None,
+                arg: Uint8Array::from(&*arg).into(),
+            };
+            self.post_request(id, request, Some(port))
+        });
 
-        // Map the receiver to extract just the response bytes.
-        let (byte_sender, byte_receiver) = oneshot::channel();
-        wasm_bindgen_futures::spawn_local(async move {
-            if let Ok(response) = receiver.await {
-                let _ = byte_sender.send(response.response.expect("Could not find function"));
+        match receiver {
+            Ok((receiver, record)) => {
+                // Map the receiver to extract just the response bytes.
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Ok(response) = receiver.await {
+                        let res = response.into_result(func_name);
+                        record.finish(&res);
+                        let _ = byte_sender.send(res.map(|res| res.to_vec()));
+                    }
+                });
+                let pending = PendingTask {
+                    id,
+                    worker: self.handle(),
+                };
+                (byte_receiver, Some(pending))
             }
-        });
+            Err(e) => {
+                let _ = byte_sender.send(Err(e));
+                (byte_receiver, None)
+            }
+        }
+    }
 
-        byte_receiver
+    /// Returns a handle to this worker that can be used to terminate it
+    /// or cancel its tasks from elsewhere.
+    pub(crate) fn handle(&self) -> WorkerHandle {
+        WorkerHandle {
+            worker: self.worker.clone(),
+            port: self.port.clone(),
+            open_tasks: Rc::clone(&self.open_tasks),
+            terminated: Rc::clone(&self.terminated),
+        }
+    }
+
+    /// Sets the [`PoolObserver`] that is notified about the tasks of this worker
+    /// and its termination, or removes it if `None` is given.
+    pub fn set_observer(&self, observer: Option<Rc<dyn PoolObserver>>) {
+        self.metrics.set_observer(observer);
     }
 
     /// Return the current capacity for new tasks.
@@ -554,11 +1166,19 @@ impl WebWorker {
     pub fn last_active(&self) -> f64 {
         self.last_active.get()
     }
+
+    /// Return whether the worker has been terminated, e.g., because
+    /// it reported an error or its wasm instance trapped.
+    /// A terminated worker fails all further tasks with [`TaskError::WorkerTerminated`].
+    pub fn is_terminated(&self) -> bool {
+        self.terminated.get()
+    }
 }
 
 impl Drop for WebWorker {
     fn drop(&mut self) {
         self.port.close();
         self.worker.terminate();
+        self.metrics.worker_terminated(self.id);
     }
 }
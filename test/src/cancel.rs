@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use wasmworker::{
+    error::TaskError, webworker, webworker_channel, webworker_channel_fn, webworker_fn, Channel,
+    WebWorker, WebWorkerPool, WorkerPoolOptions,
+};
+
+use crate::{
+    channel::Progress,
+    js_assert_eq,
+    raw::{sleep_ms, sort},
+};
+
+/// Blocks the worker for the given number of milliseconds.
+#[webworker_fn]
+pub fn busy_wait(ms: u32) -> u32 {
+    let start = js_sys::Date::now();
+    while js_sys::Date::now() - start < ms as f64 {}
+    ms
+}
+
+/// Waits until the task is cancelled.
+#[webworker_channel_fn]
+pub async fn wait_for_cancel(_arg: (), channel: Channel) -> bool {
+    channel.send(&Progress { percent: 0 });
+    channel.cancelled().await;
+    // No further messages arrive after cancellation.
+    channel.recv::<Progress>().await.is_none()
+}
+
+/// Test that in-flight tasks can be cancelled and
+/// that they no longer count towards the worker's load.
+pub(crate) async fn can_cancel_tasks() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+    let vec: Box<[u8]> = vec![8, 1, 5, 0, 4].into();
+    let sorted: Box<[u8]> = vec![0, 1, 4, 5, 8].into();
+
+    let (handle, task) = worker.run_cancellable(webworker!(busy_wait), &200);
+    let (res, _) = futures::join!(task, async {
+        sleep_ms(50).await;
+        js_assert_eq!(worker.current_load(), 1, "Task should be in flight");
+        handle.abort();
+    });
+    js_assert_eq!(
+        matches!(res, Err(TaskError::Cancelled)),
+        true,
+        "Task should be cancelled"
+    );
+    js_assert_eq!(handle.is_aborted(), true, "Handle should be aborted");
+    js_assert_eq!(worker.current_load(), 0, "Task should be removed");
+
+    // The worker finishes the cancelled task and stays usable.
+    let res = worker
+        .run_checked(webworker!(sort), &vec)
+        .await
+        .expect("Worker should still be usable");
+    js_assert_eq!(res, sorted, "Worker should still be usable");
+}
+
+/// Test that a cancelled task can terminate its worker
+/// and that the pool recreates it.
+pub(crate) async fn can_cancel_and_terminate_tasks() {
+    let pool = WebWorkerPool::with_num_workers(1)
+        .await
+        .expect("Couldn't create pool");
+    let vec: Box<[u8]> = vec![8, 1, 5, 0, 4].into();
+    let sorted: Box<[u8]> = vec![0, 1, 4, 5, 8].into();
+
+    let (handle, task) = pool.run_cancellable(webworker!(busy_wait), &10_000);
+    let (res, _) = futures::join!(task, async {
+        sleep_ms(50).await;
+        handle.abort_and_terminate();
+    });
+    js_assert_eq!(
+        matches!(res, Err(TaskError::Cancelled)),
+        true,
+        "Task should be cancelled"
+    );
+
+    // The pool does not wait for the blocked worker.
+    let res = pool
+        .run_checked(webworker!(sort), &vec)
+        .await
+        .expect("Pool should recreate the worker");
+    js_assert_eq!(res, sorted, "Task should succeed");
+    js_assert_eq!(pool.num_respawns(), 1, "Should have respawned once");
+}
+
+/// Test that channel functions can observe cancellation.
+pub(crate) async fn can_cancel_channel_task_explicitly() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    let task = worker
+        .run_channel(webworker_channel!(wait_for_cancel), &())
+        .await;
+    let progress: Progress = task.recv().await.expect("Should receive progress");
+    js_assert_eq!(progress.percent, 0, "Task should have started");
+
+    task.cancel();
+    js_assert_eq!(task.is_cancelled(), true, "Task should be cancelled");
+    js_assert_eq!(worker.current_load(), 0, "Task should be removed");
+    let res = task.result_checked().await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::Cancelled)),
+        true,
+        "Result should be cancelled"
+    );
+}
+
+/// Test that tasks exceeding their timeout fail
+/// and that the pool recreates the terminated worker.
+pub(crate) async fn can_time_out_tasks() {
+    let vec: Box<[u8]> = vec![8, 1, 5, 0, 4].into();
+    let sorted: Box<[u8]> = vec![0, 1, 4, 5, 8].into();
+    let timeout = Duration::from_millis(100);
+
+    // Tasks finishing in time are not affected.
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+    let res = worker
+        .run_with_timeout(webworker!(sort), &vec, timeout)
+        .await
+        .expect("Task should finish in time");
+    js_assert_eq!(res, sorted, "Task should finish in time");
+
+    // A runaway task terminates the worker.
+    let res = worker
+        .run_with_timeout(webworker!(busy_wait), &10_000, timeout)
+        .await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::Timeout)),
+        true,
+        "Task should time out"
+    );
+    js_assert_eq!(worker.is_terminated(), true, "Worker should be terminated");
+
+    // The pool applies its default timeout and recreates the worker.
+    let mut options = WorkerPoolOptions::new();
+    options.num_workers = Some(1);
+    options.task_timeout_ms = Some(100);
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect("Couldn't create pool");
+    let res = pool.run_checked(webworker!(busy_wait), &10_000).await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::Timeout)),
+        true,
+        "Pool task should time out"
+    );
+    let res = pool
+        .run_with_timeout(webworker!(sort), &vec, Duration::from_secs(5))
+        .await
+        .expect("Pool should recreate the worker");
+    js_assert_eq!(res, sorted, "Task should succeed after timeout");
+    js_assert_eq!(pool.num_respawns(), 1, "Should have respawned once");
+}
@@ -1,8 +1,12 @@
+use std::{cell::Cell, rc::Rc};
+
 use serde::{Deserialize, Serialize};
-use wasmworker::webworker_channel_fn;
-use wasmworker::{webworker_channel, worker_pool, Channel, WebWorker};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasmworker::{error::TrySendError, webworker, webworker_channel_fn, webworker_fn};
+use wasmworker::{webworker_channel, worker_pool, Channel, TypedChannel, WebWorker};
+use web_sys::{MessageChannel, MessageEvent};
 
-use crate::js_assert_eq;
+use crate::{js_assert_eq, raw::sleep_ms};
 
 /// Progress message sent from worker to main thread.
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
@@ -140,3 +144,255 @@ pub(crate) async fn can_use_channel_with_pool() {
     js_assert_eq!(result.items_processed, 4, "Should process all items");
     js_assert_eq!(result.was_cancelled, false, "Should not be cancelled");
 }
+
+/// The same workflow as `process_with_progress`, but with message types checked at compile time.
+#[webworker_channel_fn]
+pub async fn process_with_typed_progress(
+    data: Vec<u8>,
+    channel: TypedChannel<Continue, Progress>,
+) -> ProcessResult {
+    channel.send(&Progress { percent: 50 });
+    let should_continue = channel
+        .recv()
+        .await
+        .is_some_and(|cont| cont.should_continue);
+    if should_continue {
+        channel.send(&Progress { percent: 100 });
+    }
+    ProcessResult {
+        items_processed: if should_continue { data.len() } else { 0 },
+        was_cancelled: !should_continue,
+    }
+}
+
+pub(crate) async fn can_use_typed_channel() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    let task = worker
+        .run_channel(
+            webworker_channel!(process_with_typed_progress),
+            &vec![1, 2, 3],
+        )
+        .await;
+
+    // The message types are inferred from the function's `TypedChannel`.
+    let progress = task.recv().await.expect("Should receive 50% progress");
+    js_assert_eq!(progress.percent, 50, "Should be at 50%");
+    task.send(&Continue {
+        should_continue: true,
+    });
+    let progress = task.recv().await.expect("Should receive 100% progress");
+    js_assert_eq!(progress.percent, 100, "Should be at 100%");
+
+    let result = task.result().await;
+    js_assert_eq!(result.items_processed, 3, "Should process all items");
+}
+
+/// Sends the numbers up to `n` and returns, which closes the channel.
+#[webworker_channel_fn]
+pub async fn count_up(n: u32, channel: TypedChannel<(), u32>) {
+    for i in 0..n {
+        channel.send(&i);
+    }
+}
+
+/// Sums up all numbers until the main thread closes the channel.
+#[webworker_channel_fn]
+pub async fn sum_until_closed(_arg: (), channel: TypedChannel<u32, ()>) -> u32 {
+    let mut sum = 0;
+    while let Some(i) = channel.recv().await {
+        sum += i;
+    }
+    sum
+}
+
+pub(crate) async fn can_close_channels() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    // The channel is closed once the worker function returns.
+    let task = worker.run_channel(webworker_channel!(count_up), &5).await;
+    let mut received = vec![];
+    while let Some(i) = task.recv().await {
+        received.push(i);
+    }
+    js_assert_eq!(received, vec![0, 1, 2, 3, 4], "Should receive all messages");
+    js_assert_eq!(task.is_closed(), true, "Channel should be closed");
+    js_assert_eq!(task.try_send(&()).is_err(), true, "Sending should fail");
+    task.result().await;
+
+    // Closing the channel ends the worker's receive loop.
+    let task = worker
+        .run_channel(webworker_channel!(sum_until_closed), &())
+        .await;
+    for i in 1..=3 {
+        task.send(&i);
+    }
+    task.close();
+    let sum = task.result().await;
+    js_assert_eq!(sum, 6, "Should sum up all messages");
+}
+
+/// Sends up to `n` messages and returns how many have been sent before the channel was closed.
+#[webworker_channel_fn]
+pub async fn send_until_closed(n: u32, channel: TypedChannel<(), u32>) -> u32 {
+    for i in 0..n {
+        if channel.send_async(&i).await.is_err() {
+            return i;
+        }
+    }
+    n
+}
+
+/// Sends messages without waiting until the channel is full
+/// and returns how many have been sent.
+#[webworker_channel_fn]
+pub async fn try_send_until_full(n: u32, channel: TypedChannel<(), u32>) -> u32 {
+    for i in 0..n {
+        match channel.try_send(&i) {
+            Ok(()) => {}
+            Err(TrySendError::Full) => return i,
+            Err(e) => panic!("Unexpected error: {e}"),
+        }
+    }
+    n
+}
+
+pub(crate) async fn can_use_bounded_channels() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    // Without receiving, the worker can only send up to the capacity.
+    let task = worker
+        .run_channel_bounded(webworker_channel!(send_until_closed), &20, 4)
+        .await;
+    sleep_ms(100).await;
+    // The worker does not receive, so sending without waiting fails at the capacity.
+    for _ in 0..4 {
+        js_assert_eq!(
+            task.try_send(&()).is_ok(),
+            true,
+            "Should send up to the capacity"
+        );
+    }
+    js_assert_eq!(
+        matches!(task.try_send(&()), Err(TrySendError::Full)),
+        true,
+        "Sending should fail once the capacity is reached"
+    );
+    task.close();
+    let sent = task.result().await;
+    js_assert_eq!(sent, 4, "Worker should wait for capacity");
+
+    // Receiving grants new capacity to the worker.
+    let task = worker
+        .run_channel_bounded(webworker_channel!(send_until_closed), &20, 4)
+        .await;
+    let mut received = vec![];
+    while let Some(i) = task.recv().await {
+        received.push(i);
+    }
+    js_assert_eq!(
+        received,
+        (0..20).collect::<Vec<_>>(),
+        "Should receive all messages"
+    );
+    let sent = task.result().await;
+    js_assert_eq!(sent, 20, "Worker should send all messages");
+
+    // The same holds for the worker side if the main thread does not receive.
+    let task = worker
+        .run_channel_bounded(webworker_channel!(try_send_until_full), &20, 4)
+        .await;
+    let sent = task.result().await;
+    js_assert_eq!(sent, 4, "Worker should send up to the capacity");
+}
+
+thread_local! {
+    static FINISHED_WAITS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Waits for a single message and counts how often waiting has finished on the current worker.
+#[webworker_channel_fn]
+pub async fn wait_for_message(_arg: (), channel: TypedChannel<u32, ()>) {
+    channel.recv().await;
+    FINISHED_WAITS.with(|waits| waits.set(waits.get() + 1));
+}
+
+/// Returns how often `wait_for_message` has finished on the current worker.
+#[webworker_fn]
+pub fn finished_waits(_arg: ()) -> u32 {
+    FINISHED_WAITS.with(Cell::get)
+}
+
+/// Test that dropping a channel task ends the worker's receive loop.
+pub(crate) async fn can_drop_channel_tasks() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    let task = worker
+        .run_channel(webworker_channel!(wait_for_message), &())
+        .await;
+    sleep_ms(50).await;
+    drop(task);
+    sleep_ms(50).await;
+
+    let waits = worker.run(webworker!(finished_waits), &()).await;
+    js_assert_eq!(
+        waits,
+        1,
+        "Worker should stop waiting once the task is dropped"
+    );
+}
+
+/// Test that dropping channels releases their ports,
+/// so that messages posted afterwards are not delivered anymore.
+pub(crate) async fn can_release_channel_ports() {
+    for _ in 0..10 {
+        let msg_channel = MessageChannel::new().expect("Couldn't create MessageChannel");
+        let port = msg_channel.port1();
+        let channel = Channel::with_capacity(port.clone(), None);
+        js_assert_eq!(port.onmessage().is_some(), true, "Callback should be set");
+        drop(channel);
+        js_assert_eq!(
+            port.onmessage().is_none(),
+            true,
+            "Callback should be cleared"
+        );
+
+        // A closed port does not deliver messages to any listener.
+        let delivered = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&delivered);
+        let listener = Closure::<dyn FnMut(MessageEvent)>::new(move |_: MessageEvent| {
+            counter.set(counter.get() + 1);
+        });
+        port.add_event_listener_with_callback("message", listener.as_ref().unchecked_ref())
+            .expect("Couldn't add listener");
+        port.start();
+        msg_channel
+            .port2()
+            .post_message(&JsValue::from_str("stale"))
+            .expect("Couldn't post message");
+        sleep_ms(10).await;
+        js_assert_eq!(
+            delivered.get(),
+            0,
+            "Message should not be delivered after drop"
+        );
+        port.remove_event_listener_with_callback("message", listener.as_ref().unchecked_ref())
+            .expect("Couldn't remove listener");
+    }
+
+    // Channel tasks release their ports as well and do not interfere with each other.
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+    for n in 0..10 {
+        let task = worker.run_channel(webworker_channel!(count_up), &n).await;
+        let mut received = vec![];
+        while let Some(i) = task.recv().await {
+            received.push(i);
+        }
+        js_assert_eq!(
+            received,
+            (0..n).collect::<Vec<_>>(),
+            "Should receive own messages"
+        );
+        task.result().await;
+    }
+}
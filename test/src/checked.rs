@@ -0,0 +1,119 @@
+use wasmworker::{
+    error::TaskError, func::WebWorkerFn, webworker, webworker_fn, worker_pool, WebWorker,
+    WebWorkerPool,
+};
+
+use crate::{js_assert_eq, raw::sort};
+
+/// Test that the checked functions return errors instead of panicking
+/// and that the worker keeps working afterwards.
+pub(crate) async fn can_recover_from_task_errors() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    let vec: Box<[u8]> = vec![8, 1, 5, 0, 4].into();
+    let sorted: Box<[u8]> = vec![0, 1, 4, 5, 8].into();
+
+    // A function that has not been exported to the worker.
+    let missing: WebWorkerFn<Box<[u8]>, Box<[u8]>> =
+        WebWorkerFn::new_unchecked("does_not_exist", |v| v);
+    let res = worker.run_checked(missing, &vec).await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::FunctionNotExported("does_not_exist"))),
+        true,
+        "Should report missing function"
+    );
+
+    // A result that cannot be deserialized into the expected type.
+    let mistyped: WebWorkerFn<Box<[u8]>, [u64; 8]> = WebWorkerFn::new_unchecked("sort", |_| [0; 8]);
+    let res = worker.run_checked(mistyped, &vec).await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::Deserialization(_))),
+        true,
+        "Should report deserialization error"
+    );
+
+    // The worker is still usable.
+    let res = worker
+        .run_checked(webworker!(sort), &vec)
+        .await
+        .expect("Worker should recover");
+    js_assert_eq!(res, sorted, "Worker should recover");
+
+    // Same for the pool.
+    let pool = worker_pool().await;
+    let res = pool.run_checked(missing, &vec).await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::FunctionNotExported(_))),
+        true,
+        "Pool should report missing function"
+    );
+    let res = pool
+        .run_checked(webworker!(sort), &vec)
+        .await
+        .expect("Pool should recover");
+    js_assert_eq!(res, sorted, "Pool should recover");
+}
+
+#[webworker_fn]
+pub fn panic_on_empty(v: Box<[u8]>) -> Box<[u8]> {
+    if v.is_empty() {
+        panic!("empty input");
+    }
+    v
+}
+
+/// Test that a panic inside the worker is reported back to the caller
+/// instead of leaving the task hanging.
+pub(crate) async fn can_report_worker_panics() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    let empty: Box<[u8]> = vec![].into();
+    let res = worker.run_checked(webworker!(panic_on_empty), &empty).await;
+    let Err(TaskError::Panic { message, location }) = res else {
+        wasm_bindgen::throw_str("Should report panic");
+    };
+    js_assert_eq!(message, "empty input", "Should report panic message");
+    js_assert_eq!(
+        location
+            .as_deref()
+            .is_some_and(|l| l.contains("checked.rs")),
+        true,
+        "Should report panic location"
+    );
+}
+
+/// Test that a worker whose wasm instance trapped is terminated
+/// and that the pool transparently recreates its slot.
+pub(crate) async fn can_respawn_crashed_workers() {
+    let vec: Box<[u8]> = vec![8, 1, 5, 0, 4].into();
+    let sorted: Box<[u8]> = vec![0, 1, 4, 5, 8].into();
+    let empty: Box<[u8]> = vec![].into();
+
+    // A single worker is unusable after a trap.
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+    let _ = worker.run_checked(webworker!(panic_on_empty), &empty).await;
+    js_assert_eq!(worker.is_terminated(), true, "Worker should be terminated");
+    let res = worker.run_checked(webworker!(sort), &vec).await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::WorkerTerminated)),
+        true,
+        "Terminated worker should fail tasks"
+    );
+
+    // The pool recreates the crashed worker.
+    let pool = WebWorkerPool::with_num_workers(1)
+        .await
+        .expect("Couldn't create pool");
+    let _ = pool.run_checked(webworker!(panic_on_empty), &empty).await;
+    let res = pool
+        .run_checked(webworker!(sort), &vec)
+        .await
+        .expect("Pool should recreate the crashed worker");
+    js_assert_eq!(res, sorted, "Task should succeed after respawn");
+    js_assert_eq!(pool.num_respawns(), 1, "Should have respawned once");
+    js_assert_eq!(
+        pool.num_active_workers(),
+        1,
+        "Should have one active worker"
+    );
+}
@@ -1,6 +1,12 @@
+use js_sys::Promise;
 use serde::{Deserialize, Serialize};
+use wasm_bindgen::UnwrapThrowExt;
+use wasm_bindgen_futures::JsFuture;
 use wasmworker::webworker_fn;
-use wasmworker::{has_worker_pool, iter_ext::IteratorExt, webworker, worker_pool, WebWorker};
+use wasmworker::{
+    convert::JsonCodec, func::WebWorkerFn, has_worker_pool, iter_ext::IteratorExt, webworker,
+    worker_pool, WebWorker,
+};
 
 use crate::js_assert_eq;
 
@@ -34,6 +40,82 @@ pub(crate) async fn can_run_task() {
     js_assert_eq!(res2, sorted_vec, "Raw run failed");
 }
 
+/// A function using a different codec than the default one.
+#[webworker_fn(codec = wasmworker::convert::JsonCodec)]
+pub fn sort_vec_json(mut v: VecType) -> VecType {
+    v.0.sort();
+    v
+}
+
+/// Test that functions with different codecs can be used side by side.
+pub(crate) async fn can_mix_codecs() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    let vec = VecType(vec![8, 1, 5, 0, 4]);
+    let sorted_vec = VecType(vec![0, 1, 4, 5, 8]);
+
+    let func: WebWorkerFn<VecType, VecType, JsonCodec> = webworker!(sort_vec_json);
+    let res = worker.run(func, &vec).await;
+    js_assert_eq!(res, sorted_vec, "JSON codec run failed");
+
+    let res = worker.run(webworker!(sort_vec), &vec).await;
+    js_assert_eq!(res, sorted_vec, "Default codec run failed");
+
+    let res = worker_pool()
+        .await
+        .run(webworker!(sort_vec_json), &vec)
+        .await;
+    js_assert_eq!(res, sorted_vec, "JSON codec pool run failed");
+}
+
+#[webworker_fn]
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+#[webworker_fn]
+fn answer() -> u32 {
+    42
+}
+
+/// Test that functions with zero or multiple parameters take a tuple of arguments.
+pub(crate) async fn can_run_multi_arg_task() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    let func: WebWorkerFn<(u32, u32), u32> = webworker!(add);
+    let res = worker.run(func, &(1, 2)).await;
+    js_assert_eq!(res, 3, "Multi-argument run failed");
+
+    let res = worker.run(webworker!(answer), &()).await;
+    js_assert_eq!(res, 42, "Zero-argument run failed");
+
+    let res = [(1, 2), (3, 4)].iter().par_map(webworker!(add)).await;
+    js_assert_eq!(res, vec![3, 7], "Multi-argument par_map failed");
+}
+
+/// An async function awaiting a JS promise inside the worker.
+#[webworker_fn]
+pub async fn double_async(x: u32) -> u32 {
+    let value = JsFuture::from(Promise::resolve(&x.into()))
+        .await
+        .unwrap_throw();
+    value.as_f64().unwrap_throw() as u32 * 2
+}
+
+pub(crate) async fn can_run_async_task() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+
+    let res = worker.run(webworker!(double_async), &21).await;
+    js_assert_eq!(res, 42, "Async run failed");
+
+    let pool = worker_pool().await;
+    let res = pool.run(webworker!(double_async), &4).await;
+    js_assert_eq!(res, 8, "Async pool run failed");
+
+    let res = [1, 2, 3].iter().par_map(webworker!(double_async)).await;
+    js_assert_eq!(res, vec![2, 4, 6], "Async par_map failed");
+}
+
 pub(crate) async fn can_limit_tasks() {
     let worker = WebWorker::new(Some(0))
         .await
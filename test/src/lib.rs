@@ -1,13 +1,21 @@
+use cancel::*;
 use channel::*;
+use checked::*;
 use convert::*;
 use onmessage::*;
 use raw::*;
+use stream::*;
+use trace::*;
 use wasm_bindgen::prelude::wasm_bindgen;
 
+pub(crate) mod cancel;
 pub(crate) mod channel;
+pub(crate) mod checked;
 pub(crate) mod convert;
 pub(crate) mod onmessage;
 pub(crate) mod raw;
+pub(crate) mod stream;
+pub(crate) mod trace;
 
 #[macro_export]
 macro_rules! js_assert_eq {
@@ -30,25 +38,62 @@ pub async fn run_tests() {
     iter_ext_fallback_works().await;
     can_handle_invalid_paths().await;
     can_run_task_bytes().await;
+    can_transfer_task_bytes().await;
     can_limit_tasks_bytes().await;
     can_schedule_task_bytes().await;
     can_run_task().await;
+    can_mix_codecs().await;
+    can_run_multi_arg_task().await;
+    can_run_async_task().await;
     can_limit_tasks().await;
     can_schedule_task().await;
     can_use_iter_ext().await;
 
+    // Error handling tests
+    can_recover_from_task_errors().await;
+    can_report_worker_panics().await;
+    can_respawn_crashed_workers().await;
+
     // Channel tests
     can_use_channel_with_worker().await;
     can_cancel_channel_task().await;
     can_use_channel_with_pool().await;
+    can_use_typed_channel().await;
+    can_close_channels().await;
+    can_use_bounded_channels().await;
+    can_drop_channel_tasks().await;
+    can_release_channel_ports().await;
+
+    // Stream tests
+    can_stream_items().await;
+    can_report_stream_errors().await;
+
+    // Cancellation and timeout tests
+    can_cancel_tasks().await;
+    can_cancel_and_terminate_tasks().await;
+    can_cancel_channel_task_explicitly().await;
+    can_time_out_tasks().await;
 
     // Pool configuration tests
     can_use_precompiled_wasm().await;
     can_use_custom_pool_options().await;
+    can_use_queue_strategy().await;
+    can_prioritize_tasks().await;
+    can_limit_pool_queue().await;
+    can_route_tasks_to_workers().await;
+    can_collect_pool_stats().await;
+    can_observe_tasks().await;
 
     // Idle timeout test
     can_use_idle_timeout().await;
+    can_run_init_fn().await;
+    can_scale_pool().await;
+    can_resize_and_shut_down_pool().await;
 
     // Conflicting onmessage regression test
     can_run_task_with_conflicting_onmessage().await;
+
+    // The diagnostics tests install a global logger and subscriber and need to run last.
+    can_forward_worker_logs().await;
+    can_forward_worker_events().await;
 }
@@ -1,13 +1,30 @@
-use wasm_bindgen::{throw_str, UnwrapThrowExt};
+use std::{
+    cell::{Cell, RefCell},
+    pin::pin,
+    rc::Rc,
+    time::Duration,
+};
+
+use futures::StreamExt;
+
+use js_sys::Uint8Array;
+use wasm_bindgen::{prelude::Closure, throw_str, JsValue, UnwrapThrowExt};
 use wasm_bindgen_futures::JsFuture;
 use wasmworker::webworker_fn;
 use wasmworker::{
-    error::InitError, webworker, worker_pool, WebWorker, WebWorkerPool, WorkerPoolOptions,
+    error::{InitError, TaskError, TryRunError},
+    func::WebWorkerFn,
+    observer::{JsPoolObserver, TaskInfo},
+    pool::{Priority, SlotState, Strategy},
+    reset_worker_pool, webworker, webworker_channel, webworker_stream, worker_pool, PoolObserver,
+    WebWorker, WebWorkerPool, WorkerPoolOptions,
 };
 
-use crate::js_assert_eq;
+use crate::{
+    cancel::busy_wait, channel::count_up, checked::panic_on_empty, js_assert_eq, stream::count_to,
+};
 
-async fn sleep_ms(ms: u32) {
+pub(crate) async fn sleep_ms(ms: u32) {
     let promise = js_sys::Promise::new(&mut |resolve, _| {
         web_sys::window()
             .unwrap_throw()
@@ -52,6 +69,52 @@ pub(crate) async fn can_run_task_bytes() {
     js_assert_eq!(res2, sorted_vec, "Raw run failed");
 }
 
+pub(crate) async fn can_transfer_task_bytes() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+    let sorted_vec = vec![0, 1, 4, 5, 8];
+
+    let arg = Uint8Array::from(&[8, 1, 5, 0, 4][..]);
+    let res = worker
+        .run_transfer(webworker!(sort), arg.clone())
+        .await
+        .expect("Transfer run failed");
+    js_assert_eq!(res.to_vec(), sorted_vec, "Transfer run failed");
+    js_assert_eq!(arg.byte_length(), 0, "Argument should be transferred");
+
+    // A detached buffer cannot be transferred again, but the worker stays usable.
+    let res = worker.run_transfer(webworker!(sort), arg).await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::TransferFailed(_))),
+        true,
+        "Should report failed transfer"
+    );
+    let res = worker
+        .run_transfer(webworker!(sort), Uint8Array::from(&[2, 1][..]))
+        .await
+        .expect("Worker should still be usable");
+    js_assert_eq!(res.to_vec(), vec![1, 2], "Worker should still be usable");
+
+    // Same for the pool.
+    let arg = Uint8Array::from(&[8, 1, 5, 0, 4][..]);
+    let res = worker_pool()
+        .await
+        .run_transfer(webworker!(sort), arg)
+        .await
+        .expect("Pool transfer run failed");
+    js_assert_eq!(res.to_vec(), sorted_vec, "Pool transfer run failed");
+
+    // Functions that do not operate on raw bytes are not exported for transfers.
+    let not_raw = WebWorkerFn::new_unchecked("busy_wait", |v| v);
+    let res = worker
+        .run_transfer(not_raw, Uint8Array::new_with_length(4))
+        .await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::FunctionNotExported(_))),
+        true,
+        "Should report missing transfer function"
+    );
+}
+
 pub(crate) async fn can_limit_tasks_bytes() {
     let worker = WebWorker::new(Some(0))
         .await
@@ -126,6 +189,548 @@ pub(crate) async fn can_use_custom_pool_options() {
     js_assert_eq!(res, sorted_vec, "Custom options run failed");
 }
 
+/// Test that the queue strategy only dispatches tasks to free workers.
+pub(crate) async fn can_use_queue_strategy() {
+    let mut options = WorkerPoolOptions::new();
+    options.num_workers = Some(2);
+    options.strategy = Some(Strategy::Queue);
+
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect("Couldn't create pool with queue strategy");
+
+    let vec: Box<[u8]> = vec![3, 1, 2].into();
+    let sorted: Box<[u8]> = vec![1, 2, 3].into();
+
+    // The short tasks are not queued behind the long task.
+    let long_task = pool.run(webworker!(busy_wait), &300);
+    let short_tasks = async {
+        let mut results = Vec::new();
+        for _ in 0..3 {
+            results.push(pool.run_bytes(webworker!(sort), &vec).await);
+        }
+        js_assert_eq!(pool.current_load(), 1, "Long task should still be running");
+        results
+    };
+    let (res, results) = futures::join!(long_task, short_tasks);
+    js_assert_eq!(res, 300, "Long task failed");
+    for res in results {
+        js_assert_eq!(res, sorted, "Short task failed");
+    }
+
+    // At most one task runs per worker, further tasks wait in the queue.
+    let tasks = (0..4).map(|_| pool.run(webworker!(busy_wait), &50));
+    let (results, _) = futures::join!(futures::future::join_all(tasks), async {
+        sleep_ms(20).await;
+        js_assert_eq!(pool.current_load(), 2, "Only two tasks should run");
+    });
+    js_assert_eq!(results, vec![50; 4], "Queued tasks failed");
+}
+
+/// Test that queued tasks with a higher priority are dispatched first.
+pub(crate) async fn can_prioritize_tasks() {
+    let mut options = WorkerPoolOptions::new();
+    options.num_workers = Some(1);
+    options.strategy = Some(Strategy::Queue);
+
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect("Couldn't create pool with queue strategy");
+
+    let order = std::cell::RefCell::new(Vec::new());
+    let run = |ms: u32, priority: Priority| {
+        let order = &order;
+        let pool = &pool;
+        async move {
+            let res = pool
+                .run_with_priority(webworker!(busy_wait), &ms, priority)
+                .await
+                .expect("Task failed");
+            order.borrow_mut().push(priority);
+            res
+        }
+    };
+
+    // The first task occupies the worker while the others wait in the queue.
+    futures::join!(
+        run(100, Priority::Normal),
+        run(10, Priority::Low),
+        run(10, Priority::Normal),
+        run(10, Priority::High),
+    );
+    let order = order.into_inner();
+    js_assert_eq!(
+        order,
+        vec![
+            Priority::Normal,
+            Priority::High,
+            Priority::Normal,
+            Priority::Low
+        ],
+        "Tasks should be dispatched by priority"
+    );
+}
+
+/// Test that a saturated pool rejects tasks via `try_run`.
+pub(crate) async fn can_limit_pool_queue() {
+    let mut options = WorkerPoolOptions::new();
+    options.num_workers = Some(1);
+    options.worker_concurrency = Some(1);
+    options.max_queued_tasks = Some(1);
+
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect("Couldn't create pool with bounded queue");
+
+    // The first task runs, the second one waits and the third one is rejected.
+    let (running, queued, rejected) = futures::join!(
+        pool.run(webworker!(busy_wait), &100),
+        pool.try_run(webworker!(busy_wait), &10),
+        pool.try_run(webworker!(busy_wait), &10),
+    );
+    js_assert_eq!(running, 100, "Running task failed");
+    let queued = queued.expect("Queued task should not be rejected");
+    js_assert_eq!(queued, 10, "Queued task failed");
+    js_assert_eq!(
+        matches!(rejected, Err(TryRunError::Full(_))),
+        true,
+        "Task should be rejected"
+    );
+
+    // Once the pool is idle again, tasks are accepted.
+    let vec: Box<[u8]> = vec![3, 1, 2].into();
+    let sorted: Box<[u8]> = vec![1, 2, 3].into();
+    let res = pool
+        .try_run_bytes(webworker!(sort), &vec)
+        .await
+        .expect("Pool should not be full");
+    js_assert_eq!(res, sorted, "Task should succeed");
+
+    // Failing tasks are reported instead of panicking.
+    let empty: Box<[u8]> = vec![].into();
+    let res = pool.try_run(webworker!(panic_on_empty), &empty).await;
+    js_assert_eq!(
+        matches!(res, Err(TryRunError::Task(TaskError::Panic { .. }))),
+        true,
+        "Task failure should be reported"
+    );
+
+    // A queue limit alone restricts each worker to one task at a time.
+    let mut options = WorkerPoolOptions::new();
+    options.num_workers = Some(1);
+    options.max_queued_tasks = Some(0);
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect("Couldn't create pool with bounded queue");
+    let (running, rejected) = futures::join!(
+        pool.run(webworker!(busy_wait), &100),
+        pool.try_run(webworker!(busy_wait), &10),
+    );
+    js_assert_eq!(running, 100, "Running task failed");
+    js_assert_eq!(
+        matches!(rejected, Err(TryRunError::Full(_))),
+        true,
+        "Task should be rejected without explicit concurrency"
+    );
+}
+
+thread_local! {
+    static CALLS: std::cell::Cell<u32> = const { std::cell::Cell::new(0) };
+}
+
+/// Counts the calls on the current worker.
+#[webworker_fn]
+pub fn count_calls(_arg: ()) -> u32 {
+    CALLS.with(|calls| {
+        calls.set(calls.get() + 1);
+        calls.get()
+    })
+}
+
+/// Test that tasks can be routed to specific workers.
+pub(crate) async fn can_route_tasks_to_workers() {
+    let pool = WebWorkerPool::with_num_workers(2)
+        .await
+        .expect("Couldn't create pool");
+
+    for expected in 1..=3 {
+        let calls = pool
+            .run_on(1, webworker!(count_calls), &())
+            .await
+            .expect("Task failed");
+        js_assert_eq!(calls, expected, "Tasks should run on the same worker");
+    }
+    let calls = pool
+        .run_on(0, webworker!(count_calls), &())
+        .await
+        .expect("Task failed");
+    js_assert_eq!(calls, 1, "Task should run on the other worker");
+
+    // Indices beyond the pool's size are rejected.
+    let res = pool.run_on(2, webworker!(count_calls), &()).await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::InvalidWorker(2))),
+        true,
+        "Should reject an invalid worker index"
+    );
+
+    // Tasks with the same key share a worker.
+    let first = pool
+        .run_with_affinity("key", webworker!(count_calls), &())
+        .await
+        .expect("Task failed");
+    let second = pool
+        .run_with_affinity("key", webworker!(count_calls), &())
+        .await
+        .expect("Task failed");
+    js_assert_eq!(second, first + 1, "Tasks should run on the same worker");
+}
+
+/// Test that an elastic pool grows on load and shrinks back to its minimum size.
+pub(crate) async fn can_scale_pool() {
+    let mut options = WorkerPoolOptions::new();
+    options.min_workers = Some(1);
+    options.max_workers = Some(3);
+    options.idle_timeout_ms = Some(300);
+
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect("Couldn't create elastic pool");
+
+    js_assert_eq!(pool.num_workers(), 3, "Should have 3 worker slots");
+    js_assert_eq!(
+        pool.num_active_workers(),
+        1,
+        "Should start with the minimum number of workers"
+    );
+
+    // Concurrent tasks start another worker in the background.
+    let tasks = (0..3).map(|_| pool.run(webworker!(busy_wait), &100));
+    let results = futures::future::join_all(tasks).await;
+    js_assert_eq!(results, vec![100; 3], "Tasks failed");
+    let deadline = js_sys::Date::now() + 10_000.0;
+    while pool.num_active_workers() < 2 && js_sys::Date::now() < deadline {
+        sleep_ms(20).await;
+    }
+    js_assert_eq!(
+        pool.num_active_workers() >= 2,
+        true,
+        "Pool should have grown on load"
+    );
+
+    // Idle workers are terminated down to the minimum.
+    let deadline = js_sys::Date::now() + 10_000.0;
+    while pool.num_active_workers() > 1 && js_sys::Date::now() < deadline {
+        sleep_ms(50).await;
+    }
+    sleep_ms(600).await;
+    js_assert_eq!(
+        pool.num_active_workers(),
+        1,
+        "Pool should shrink to the minimum number of workers"
+    );
+}
+
+/// Test that pools can be resized and shut down.
+pub(crate) async fn can_resize_and_shut_down_pool() {
+    let pool = WebWorkerPool::with_num_workers(2)
+        .await
+        .expect("Couldn't create pool");
+
+    pool.resize(3).await.expect("Couldn't grow pool");
+    js_assert_eq!(pool.num_workers(), 3, "Pool should have grown");
+    js_assert_eq!(pool.num_active_workers(), 3, "New workers should be active");
+
+    // Shrinking waits for the in-flight tasks of the removed workers.
+    let (results, _) = futures::join!(
+        futures::future::join_all((0..3).map(|_| pool.run(webworker!(busy_wait), &50))),
+        pool.resize(1),
+    );
+    js_assert_eq!(results, vec![50; 3], "In-flight tasks should finish");
+    js_assert_eq!(pool.num_workers(), 1, "Pool should have shrunk");
+
+    // Shutting down drains in-flight tasks and rejects new ones.
+    let (res, _) = futures::join!(pool.run(webworker!(busy_wait), &50), pool.shutdown());
+    js_assert_eq!(res, 50, "In-flight task should finish");
+    js_assert_eq!(pool.is_shut_down(), true, "Pool should be shut down");
+    js_assert_eq!(pool.num_active_workers(), 0, "Workers should be terminated");
+    let res = pool.run_checked(webworker!(busy_wait), &0).await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::PoolShutdown)),
+        true,
+        "New tasks should be rejected"
+    );
+    let res = pool.try_run(webworker!(busy_wait), &0).await;
+    js_assert_eq!(
+        matches!(res, Err(TryRunError::Task(TaskError::PoolShutdown))),
+        true,
+        "New tasks should not be reported as full"
+    );
+    let res = pool
+        .run_channel_checked(webworker_channel!(count_up), &1)
+        .await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::PoolShutdown)),
+        true,
+        "New channel tasks should be rejected"
+    );
+    let mut items = pin!(
+        pool.run_stream_checked(webworker_stream!(count_to), &1)
+            .await
+    );
+    js_assert_eq!(
+        matches!(items.next().await, Some(Err(TaskError::PoolShutdown))),
+        true,
+        "New stream tasks should be rejected"
+    );
+    js_assert_eq!(
+        items.next().await.is_none(),
+        true,
+        "Stream should end after the error"
+    );
+    let res = pool.resize(2).await;
+    js_assert_eq!(
+        matches!(res, Err(InitError::PoolShutdown)),
+        true,
+        "Shut down pool should not be resized"
+    );
+    js_assert_eq!(pool.num_workers(), 1, "Shut down pool should keep its size");
+
+    // Terminating fails in-flight tasks.
+    let pool = WebWorkerPool::with_num_workers(1)
+        .await
+        .expect("Couldn't create pool");
+    let (res, _) = futures::join!(pool.run_checked(webworker!(busy_wait), &200), async {
+        sleep_ms(50).await;
+        pool.terminate_now();
+    });
+    js_assert_eq!(
+        matches!(res, Err(TaskError::WorkerTerminated)),
+        true,
+        "In-flight task should fail"
+    );
+
+    // The global pool can be replaced.
+    let previous = worker_pool().await;
+    reset_worker_pool().await;
+    js_assert_eq!(
+        previous.is_shut_down(),
+        true,
+        "Previous pool should be shut down"
+    );
+    let pool = worker_pool().await;
+    js_assert_eq!(pool.is_shut_down(), false, "Pool should be recreated");
+    let res = pool.run(webworker!(busy_wait), &0).await;
+    js_assert_eq!(res, 0, "Recreated pool should run tasks");
+}
+
+/// Test that pools report their state and task statistics.
+pub(crate) async fn can_collect_pool_stats() {
+    let mut options = WorkerPoolOptions::new();
+    options.num_workers = Some(1);
+    options.strategy = Some(Strategy::Queue);
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect("Couldn't create pool");
+
+    let stats = pool.stats();
+    js_assert_eq!(
+        stats.slots,
+        vec![SlotState::Active],
+        "Worker should be active"
+    );
+    js_assert_eq!(stats.workers_created, 1, "One worker should be created");
+
+    // One task runs while the others wait in the queue.
+    let (results, stats) = futures::join!(
+        futures::future::join_all((0..3).map(|_| pool.run(webworker!(busy_wait), &50))),
+        async {
+            sleep_ms(20).await;
+            pool.stats()
+        },
+    );
+    js_assert_eq!(results, vec![50; 3], "Tasks should finish");
+    js_assert_eq!(stats.in_flight_tasks, 1, "One task should be in flight");
+    js_assert_eq!(stats.queued_tasks, 2, "Two tasks should be queued");
+
+    let stats = pool.stats();
+    js_assert_eq!(stats.in_flight_tasks, 0, "No task should be in flight");
+    js_assert_eq!(stats.completed_tasks, 3, "Tasks should be completed");
+    js_assert_eq!(
+        stats.functions.len(),
+        1,
+        "Only one function should be listed"
+    );
+    let function = &stats.functions[0];
+    js_assert_eq!(
+        function.name,
+        "busy_wait",
+        "Function should be listed by name"
+    );
+    js_assert_eq!(
+        function.completed_tasks,
+        3,
+        "Tasks should be counted per function"
+    );
+    js_assert_eq!(
+        function.average_ms >= 50.0,
+        true,
+        "Average run time is too low"
+    );
+    js_assert_eq!(
+        function.p95_ms >= function.average_ms,
+        true,
+        "p95 is too low"
+    );
+    js_assert_eq!(stats.bytes_sent > 0, true, "Arguments should be counted");
+    js_assert_eq!(stats.bytes_received > 0, true, "Results should be counted");
+
+    // A timed out task fails and terminates its worker.
+    let res = pool
+        .run_with_timeout(webworker!(busy_wait), &200, Duration::from_millis(50))
+        .await;
+    js_assert_eq!(
+        matches!(res, Err(TaskError::Timeout)),
+        true,
+        "Task should time out"
+    );
+    let stats = pool.stats();
+    js_assert_eq!(stats.failed_tasks, 1, "Timed out task should be counted");
+    js_assert_eq!(
+        stats.slots,
+        vec![SlotState::Empty],
+        "Worker should be terminated"
+    );
+
+    // The worker is recreated for the next task.
+    pool.run(webworker!(busy_wait), &0).await;
+    let stats = pool.stats();
+    js_assert_eq!(stats.workers_created, 2, "Worker should be recreated");
+    js_assert_eq!(
+        stats.workers_terminated,
+        1,
+        "Worker termination should be counted"
+    );
+}
+
+/// An observer that records the events it receives.
+#[derive(Default)]
+struct RecordingObserver {
+    events: RefCell<Vec<String>>,
+}
+
+impl RecordingObserver {
+    fn record(&self, event: &str, task: &TaskInfo) {
+        self.events
+            .borrow_mut()
+            .push(format!("{event} {} {}", task.func_name, task.id));
+    }
+
+    fn take(&self) -> Vec<String> {
+        self.events.take()
+    }
+}
+
+impl PoolObserver for RecordingObserver {
+    fn on_task_submitted(&self, task: &TaskInfo) {
+        self.record("submitted", task);
+    }
+
+    fn on_task_started(&self, task: &TaskInfo) {
+        self.record("started", task);
+    }
+
+    fn on_task_completed(&self, task: &TaskInfo, _duration: Duration, _bytes: usize) {
+        self.record("completed", task);
+    }
+
+    fn on_task_failed(&self, task: &TaskInfo, _error: &TaskError) {
+        self.record("failed", task);
+    }
+
+    fn on_worker_spawned(&self, worker_id: usize) {
+        self.events
+            .borrow_mut()
+            .push(format!("spawned {worker_id}"));
+    }
+
+    fn on_worker_terminated(&self, worker_id: usize) {
+        self.events
+            .borrow_mut()
+            .push(format!("terminated {worker_id}"));
+    }
+}
+
+/// Test that observers are notified about tasks and workers.
+pub(crate) async fn can_observe_tasks() {
+    let pool = WebWorkerPool::with_num_workers(1)
+        .await
+        .expect("Couldn't create pool");
+    let observer = Rc::new(RecordingObserver::default());
+    pool.set_observer(Some(observer.clone()));
+
+    pool.run(webworker!(busy_wait), &10).await;
+    js_assert_eq!(
+        observer.take(),
+        vec![
+            "submitted busy_wait 0",
+            "started busy_wait 0",
+            "completed busy_wait 0"
+        ],
+        "Task events should be reported"
+    );
+
+    // A timed out task fails and its worker is recreated for the next task.
+    let _ = pool
+        .run_with_timeout(webworker!(busy_wait), &200, Duration::from_millis(50))
+        .await;
+    pool.run(webworker!(busy_wait), &0).await;
+    js_assert_eq!(
+        observer.take(),
+        vec![
+            "submitted busy_wait 1",
+            "started busy_wait 1",
+            "failed busy_wait 1",
+            "terminated 0",
+            "spawned 1",
+            "submitted busy_wait 2",
+            "started busy_wait 2",
+            "completed busy_wait 2"
+        ],
+        "Worker events should be reported"
+    );
+
+    // Observers can also be JavaScript objects.
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+    let completed = Rc::new(Cell::new(0));
+    let counter = Rc::clone(&completed);
+    let callback = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+        let name = js_sys::Reflect::get(&event, &JsValue::from_str("funcName")).unwrap_throw();
+        if name.as_string().as_deref() == Some("busy_wait") {
+            counter.set(counter.get() + 1);
+        }
+    });
+    let object = js_sys::Object::new();
+    js_sys::Reflect::set(
+        &object,
+        &JsValue::from_str("onTaskCompleted"),
+        callback.as_ref(),
+    )
+    .unwrap_throw();
+    worker.set_observer(Some(Rc::new(JsPoolObserver::new(object.into()))));
+    worker.run(webworker!(busy_wait), &0).await;
+    js_assert_eq!(completed.get(), 1, "JavaScript observer should be called");
+
+    // Observers can be removed.
+    pool.set_observer(None);
+    pool.run(webworker!(busy_wait), &0).await;
+    js_assert_eq!(
+        observer.take().len(),
+        0,
+        "Removed observer should not be called"
+    );
+}
+
 /// Test that idle timeout terminates workers and transparently recreates them.
 pub(crate) async fn can_use_idle_timeout() {
     let mut options = WorkerPoolOptions::new();
@@ -172,4 +777,71 @@ pub(crate) async fn can_use_idle_timeout() {
         true,
         "Should have at least one active worker after recreation"
     );
+
+    // Routed tasks recreate their own slot.
+    pool.run_on(1, webworker!(sort), &vec)
+        .await
+        .expect("Routed task should succeed after idle termination");
+    js_assert_eq!(
+        pool.num_active_workers(),
+        2,
+        "Routed task should recreate its worker"
+    );
+}
+
+thread_local! {
+    static INIT_VALUE: Cell<u32> = const { Cell::new(0) };
+}
+
+#[webworker_fn]
+pub fn set_init_value(value: u32) {
+    if value == 0 {
+        panic!("invalid init value");
+    }
+    INIT_VALUE.with(|init_value| init_value.set(value));
+}
+
+#[webworker_fn]
+pub fn init_value(_arg: ()) -> u32 {
+    INIT_VALUE.with(Cell::get)
+}
+
+/// Test that the init function runs in every worker, including recreated ones,
+/// and that its failure is reported on creation.
+pub(crate) async fn can_run_init_fn() {
+    let mut options = WorkerPoolOptions::new();
+    options.num_workers = Some(1);
+    options.idle_timeout_ms = Some(300);
+    options
+        .set_init_fn(webworker!(set_init_value), &42)
+        .expect("Couldn't set init function");
+
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect("Couldn't create pool with init function");
+    let res = pool.run(webworker!(init_value), &()).await;
+    js_assert_eq!(res, 42, "Init function should run before tasks");
+
+    // Wait until the worker is idle-terminated.
+    let deadline = js_sys::Date::now() + 10_000.0;
+    while pool.num_active_workers() > 0 && js_sys::Date::now() < deadline {
+        sleep_ms(50).await;
+    }
+    js_assert_eq!(pool.num_active_workers(), 0, "Worker should be terminated");
+
+    let res = pool.run(webworker!(init_value), &()).await;
+    js_assert_eq!(res, 42, "Init function should run in recreated worker");
+
+    // A panicking init function fails the pool creation.
+    let mut options = WorkerPoolOptions::new();
+    options.num_workers = Some(1);
+    options
+        .set_init_fn(webworker!(set_init_value), &0)
+        .expect("Couldn't set init function");
+    let res = WebWorkerPool::with_options(options).await;
+    js_assert_eq!(
+        matches!(res, Err(InitError::InitFunction(ref message)) if message.contains("invalid init value")),
+        true,
+        "Init function panic should be reported"
+    );
 }
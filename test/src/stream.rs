@@ -0,0 +1,75 @@
+use std::pin::pin;
+
+use futures::{stream, Stream, StreamExt};
+use wasmworker::{error::TaskError, webworker_stream, webworker_stream_fn, worker_pool, WebWorker};
+
+use crate::js_assert_eq;
+
+/// A stream producing more items than the worker may send ahead of the consumer.
+#[webworker_stream_fn]
+pub fn count_to(n: u32) -> impl Stream<Item = u32> {
+    stream::iter(1..=n)
+}
+
+/// A stream that panics after producing `n` items.
+#[webworker_stream_fn]
+pub fn panic_after(n: u32) -> impl Stream<Item = u32> {
+    stream::iter(0..).map(move |i| {
+        if i == n {
+            panic!("stream failed");
+        }
+        i
+    })
+}
+
+pub(crate) async fn can_stream_items() {
+    let expected: Vec<u32> = (1..=40).collect();
+
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+    let items: Vec<u32> = worker
+        .run_stream(webworker_stream!(count_to), &40)
+        .await
+        .collect()
+        .await;
+    js_assert_eq!(items, expected, "Worker stream failed");
+
+    let pool = worker_pool().await;
+    let items: Vec<u32> = pool
+        .run_stream(webworker_stream!(count_to), &40)
+        .await
+        .collect()
+        .await;
+    js_assert_eq!(items, expected, "Pool stream failed");
+
+    // Dropping a stream early stops the worker, which remains usable.
+    let items: Vec<u32> = worker
+        .run_stream(webworker_stream!(count_to), &u32::MAX)
+        .await
+        .take(3)
+        .collect()
+        .await;
+    js_assert_eq!(items, vec![1, 2, 3], "Partial stream failed");
+    let items: Vec<u32> = worker
+        .run_stream(webworker_stream!(count_to), &2)
+        .await
+        .collect()
+        .await;
+    js_assert_eq!(items, vec![1, 2], "Stream after drop failed");
+}
+
+pub(crate) async fn can_report_stream_errors() {
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+    let mut items = pin!(
+        worker
+            .run_stream_checked(webworker_stream!(panic_after), &2)
+            .await
+    );
+
+    js_assert_eq!(items.next().await.map(Result::ok), Some(Some(0)));
+    js_assert_eq!(items.next().await.map(Result::ok), Some(Some(1)));
+    let Some(Err(TaskError::Panic { message, .. })) = items.next().await else {
+        wasm_bindgen::throw_str("Should report panic");
+    };
+    js_assert_eq!(message, "stream failed", "Should report panic message");
+    js_assert_eq!(items.next().await.is_none(), true, "Stream should end");
+}
@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use log::{Level, LevelFilter, Log};
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Instrument, Metadata, Subscriber,
+};
+use wasmworker::{pool::WorkerPoolOptions, webworker, webworker_fn, WebWorker, WebWorkerPool};
+
+use crate::js_assert_eq;
+
+/// A span recorded by the [`Recorder`].
+struct RecordedSpan {
+    name: &'static str,
+    parent: Option<u64>,
+    fields: HashMap<&'static str, String>,
+}
+
+/// An event recorded by the [`Recorder`].
+struct RecordedEvent {
+    target: String,
+    span: Option<u64>,
+    fields: HashMap<&'static str, String>,
+}
+
+#[derive(Default)]
+struct Recording {
+    spans: HashMap<u64, RecordedSpan>,
+    entered: Vec<u64>,
+    events: Vec<RecordedEvent>,
+}
+
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+fn with_recording<R>(f: impl FnOnce(&mut Recording) -> R) -> R {
+    let mut recording = RECORDING.lock().unwrap();
+    f(recording.get_or_insert_with(Recording::default))
+}
+
+/// A subscriber that records all spans and events on the main thread.
+#[derive(Default)]
+struct Recorder {
+    last_span: AtomicU64,
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.last_span.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut visitor = FieldVisitor::default();
+        span.record(&mut visitor);
+        with_recording(|recording| {
+            let parent = match span.parent() {
+                Some(parent) => Some(parent.into_u64()),
+                None if span.is_contextual() => recording.entered.last().copied(),
+                None => None,
+            };
+            recording.spans.insert(
+                id,
+                RecordedSpan {
+                    name: span.metadata().name(),
+                    parent,
+                    fields: visitor.0,
+                },
+            );
+        });
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        with_recording(|recording| {
+            let span = recording.entered.last().copied();
+            recording.events.push(RecordedEvent {
+                target: event.metadata().target().to_string(),
+                span,
+                fields: visitor.0,
+            });
+        });
+    }
+
+    fn enter(&self, span: &Id) {
+        with_recording(|recording| recording.entered.push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        with_recording(|recording| {
+            if let Some(index) = recording
+                .entered
+                .iter()
+                .rposition(|id| *id == span.into_u64())
+            {
+                recording.entered.remove(index);
+            }
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor(HashMap<&'static str, String>);
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name(), format!("{value:?}"));
+    }
+}
+
+#[webworker_fn]
+pub fn traced_reverse(mut v: Box<[u8]>) -> Box<[u8]> {
+    let _span = tracing::info_span!("reverse", len = v.len()).entered();
+    tracing::info!(first = v[0], "reversing");
+    v.reverse();
+    v
+}
+
+/// Test that spans and events created inside a worker are forwarded to the main thread
+/// and recreated below the task's span, which is a child of the caller's span.
+pub(crate) async fn can_forward_worker_events() {
+    tracing::subscriber::set_global_default(Recorder::default())
+        .expect("Couldn't set global subscriber");
+
+    let worker = WebWorker::new(None).await.expect("Couldn't create worker");
+    let caller = tracing::info_span!("caller");
+    let res = worker
+        .run(webworker!(traced_reverse), &[1u8, 2, 3][..].into())
+        .instrument(caller.clone())
+        .await;
+    js_assert_eq!(res, Box::<[u8]>::from([3u8, 2, 1]));
+
+    with_recording(|recording| {
+        let event = recording
+            .events
+            .iter()
+            .find(|event| event.target == "wasmworker::worker")
+            .expect("Worker event should be forwarded");
+        js_assert_eq!(
+            event.fields.get("message").map(String::as_str),
+            Some("reversing first=1")
+        );
+
+        let worker_span = event
+            .span
+            .and_then(|id| recording.spans.get(&id))
+            .expect("Worker event should be emitted inside a span");
+        js_assert_eq!(worker_span.name.to_string(), "worker_span");
+        js_assert_eq!(
+            worker_span.fields.get("worker_span").map(String::as_str),
+            Some("reverse")
+        );
+        js_assert_eq!(
+            worker_span.fields.get("worker_target").map(String::as_str),
+            Some("wasmworker_test::trace")
+        );
+        js_assert_eq!(
+            worker_span.fields.get("fields").map(String::as_str),
+            Some("len=3")
+        );
+
+        let task_span = worker_span
+            .parent
+            .and_then(|id| recording.spans.get(&id))
+            .expect("Worker span should be a child of the task span");
+        js_assert_eq!(task_span.name.to_string(), "webworker_task");
+        js_assert_eq!(task_span.parent, caller.id().map(|id| id.into_u64()));
+    });
+}
+
+/// A logger that records all log records on the main thread.
+struct RecordingLogger;
+
+static LOG_RECORDS: Mutex<Vec<(Level, String, String)>> = Mutex::new(Vec::new());
+
+impl Log for RecordingLogger {
+    fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        LOG_RECORDS.lock().unwrap().push((
+            record.level(),
+            record.target().to_string(),
+            record.args().to_string(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+#[webworker_fn]
+pub fn logged_reverse(mut v: Box<[u8]>) -> Box<[u8]> {
+    log::info!("reversing {} bytes", v.len());
+    log::debug!("reversed");
+    v.reverse();
+    v
+}
+
+/// Test that log records emitted inside a worker are forwarded to the main thread
+/// up to the main thread's maximum level.
+pub(crate) async fn can_forward_worker_logs() {
+    log::set_logger(&RecordingLogger).expect("Couldn't set logger");
+    log::set_max_level(LevelFilter::Info);
+
+    let mut options = WorkerPoolOptions::new();
+    options.num_workers = Some(1);
+    options.forward_logs = Some(true);
+    let pool = WebWorkerPool::with_options(options)
+        .await
+        .expect("Couldn't create pool");
+
+    let res = pool
+        .run(webworker!(logged_reverse), &[1u8, 2, 3][..].into())
+        .await;
+    js_assert_eq!(res, Box::<[u8]>::from([3u8, 2, 1]));
+
+    let records = LOG_RECORDS.lock().unwrap();
+    let records = records
+        .iter()
+        .filter(|(_, target, _)| target == "wasmworker_test::trace")
+        .collect::<Vec<_>>();
+    js_assert_eq!(
+        records,
+        vec![&(
+            Level::Info,
+            "wasmworker_test::trace".to_string(),
+            "[worker 0, task 0] reversing 3 bytes".to_string()
+        )],
+        "Only the info record should be forwarded"
+    );
+}